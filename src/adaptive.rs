@@ -0,0 +1,279 @@
+use crate::{BitVector, IndexedBitVector, RrrBitVector, RunLengthBitVector, SparseBitVector};
+
+/// The representations an `AdaptiveBitVector` can choose from
+/// They are ordered from the fastest to the slowest queries by a heuristic, the order was not measured for particular bits:
+/// plain queries use the accelerators directly, sparse queries additionally combine high and low parts,
+/// RRR queries decode blocks after a sample and run-length queries combine two sparse vectors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Representation {
+    /// A plain `BitVector` with all accelerators
+    Plain,
+    /// An Elias-Fano encoded `SparseBitVector`
+    Sparse,
+    /// A block compressed `RrrBitVector`
+    Rrr,
+    /// A `RunLengthBitVector`
+    RunLength,
+}
+
+/// The chosen representation together with its data
+/// The variants are boxed because plain bit vectors are much larger than their handles.
+enum Storage {
+    Plain(Box<IndexedBitVector>),
+    Sparse(Box<SparseBitVector>),
+    Rrr(Box<RrrBitVector>),
+    RunLength(Box<RunLengthBitVector>),
+}
+
+/// A bit vector which chooses its representation based on the bits it stores
+/// Vectors with few ones are stored as `SparseBitVector`, vectors with few long runs as `RunLengthBitVector`
+/// and dense vectors as `RrrBitVector`, or as plain `BitVector` if there is enough space.
+/// To choose, the sizes of all representations are estimated from the length, the number of ones and the number of runs of ones,
+/// and only the chosen representation is built.
+pub struct AdaptiveBitVector {
+    /// The chosen representation
+    storage: Storage,
+}
+
+impl AdaptiveBitVector {
+    /// Builds the representation of `bits` with the smallest estimated size
+    pub fn build(bits: BitVector) -> Self {
+        let estimates = Self::estimate_sizes(&bits);
+        let (representation, _) = estimates.into_iter().min_by_key(|&(_, size)| size).unwrap();
+        Self::build_representation(bits, representation)
+    }
+
+    /// Builds the fastest representation of `bits` whose estimated size is at most `budget` bytes
+    /// The speed follows the heuristic order of `Representation`.
+    /// If no representation fits into the budget the smallest one is chosen.
+    pub fn build_with_budget(bits: BitVector, budget: usize) -> Self {
+        let estimates = Self::estimate_sizes(&bits);
+        let (representation, _) = estimates.into_iter()
+            .find(|&(_, size)| size <= budget)
+            .unwrap_or_else(|| estimates.into_iter().min_by_key(|&(_, size)| size).unwrap());
+        Self::build_representation(bits, representation)
+    }
+
+    /// Estimate the sizes of all representations of `bits` in the order of `Representation`
+    fn estimate_sizes(bits: &BitVector) -> [(Representation, usize); 4] {
+        let len = bits.len();
+        let ones = bits.count_ones(0..len);
+        let runs = bits.runs().filter(|&(bit, _, _)| bit).count();
+        [
+            (Representation::Plain, IndexedBitVector::estimate_size(len, ones)),
+            (Representation::Sparse, SparseBitVector::estimate_size(len, ones)),
+            (Representation::Rrr, RrrBitVector::estimate_size(len, ones, runs)),
+            (Representation::RunLength, RunLengthBitVector::estimate_size(len, ones, runs)),
+        ]
+    }
+
+    /// Builds the `representation` of `bits`
+    fn build_representation(bits: BitVector, representation: Representation) -> Self {
+        let storage = match representation {
+            Representation::Plain => Storage::Plain(Box::new(bits.build())),
+            Representation::Sparse => Storage::Sparse(Box::new(SparseBitVector::from(&bits))),
+            Representation::Rrr => Storage::Rrr(Box::new(RrrBitVector::from(&bits))),
+            Representation::RunLength => Storage::RunLength(Box::new(RunLengthBitVector::from(&bits))),
+        };
+        AdaptiveBitVector { storage }
+    }
+
+    /// Get the chosen representation
+    pub fn representation(&self) -> Representation {
+        match self.storage {
+            Storage::Plain(_) => Representation::Plain,
+            Storage::Sparse(_) => Representation::Sparse,
+            Storage::Rrr(_) => Representation::Rrr,
+            Storage::RunLength(_) => Representation::RunLength,
+        }
+    }
+
+    /// Get the size of the bit vector including space on the heap
+    pub fn get_size(&self) -> usize {
+        self.storage.get_size()
+    }
+
+    /// Get the length of the vector
+    #[inline]
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Plain(bit_vector) => bit_vector.len(),
+            Storage::Sparse(bit_vector) => bit_vector.len(),
+            Storage::Rrr(bit_vector) => bit_vector.len(),
+            Storage::RunLength(bit_vector) => bit_vector.len(),
+        }
+    }
+
+    /// Whether the vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the bit at `index`
+    #[inline]
+    pub fn access(&self, index: usize) -> usize {
+        match &self.storage {
+            Storage::Plain(bit_vector) => bit_vector.access(index),
+            Storage::Sparse(bit_vector) => bit_vector.access(index),
+            Storage::Rrr(bit_vector) => bit_vector.access(index),
+            Storage::RunLength(bit_vector) => bit_vector.access(index),
+        }
+    }
+
+    /// Get the number of zeros before `index`, i.e. in `0..index`
+    #[inline]
+    pub fn rank0(&self, index: usize) -> usize {
+        match &self.storage {
+            Storage::Plain(bit_vector) => bit_vector.rank0(index),
+            Storage::Sparse(bit_vector) => bit_vector.rank0(index),
+            Storage::Rrr(bit_vector) => bit_vector.rank0(index),
+            Storage::RunLength(bit_vector) => bit_vector.rank0(index),
+        }
+    }
+
+    /// Get the number of ones before `index`, i.e. in `0..index`
+    #[inline]
+    pub fn rank1(&self, index: usize) -> usize {
+        match &self.storage {
+            Storage::Plain(bit_vector) => bit_vector.rank1(index),
+            Storage::Sparse(bit_vector) => bit_vector.rank1(index),
+            Storage::Rrr(bit_vector) => bit_vector.rank1(index),
+            Storage::RunLength(bit_vector) => bit_vector.rank1(index),
+        }
+    }
+
+    /// Get the position of the `index`-th zero, counting from zero
+    /// Returns `None` if the vector contains at most `index` zeros.
+    #[inline]
+    pub fn select0(&self, index: usize) -> Option<usize> {
+        match &self.storage {
+            Storage::Plain(bit_vector) => bit_vector.select0(index),
+            Storage::Sparse(bit_vector) => bit_vector.select0(index),
+            Storage::Rrr(bit_vector) => bit_vector.select0(index),
+            Storage::RunLength(bit_vector) => bit_vector.select0(index),
+        }
+    }
+
+    /// Get the position of the `index`-th one, counting from zero
+    /// Returns `None` if the vector contains at most `index` ones.
+    #[inline]
+    pub fn select1(&self, index: usize) -> Option<usize> {
+        match &self.storage {
+            Storage::Plain(bit_vector) => bit_vector.select1(index),
+            Storage::Sparse(bit_vector) => bit_vector.select1(index),
+            Storage::Rrr(bit_vector) => bit_vector.select1(index),
+            Storage::RunLength(bit_vector) => bit_vector.select1(index),
+        }
+    }
+}
+
+impl Storage {
+    /// Get the size of the representation including space on the heap
+    fn get_size(&self) -> usize {
+        match self {
+            Storage::Plain(bit_vector) => bit_vector.get_size(),
+            Storage::Sparse(bit_vector) => bit_vector.get_size(),
+            Storage::Rrr(bit_vector) => bit_vector.get_size(),
+            Storage::RunLength(bit_vector) => bit_vector.get_size(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::{AdaptiveBitVector, BitVector, IndexedBitVector, Representation, RrrBitVector, RunLengthBitVector, SparseBitVector};
+
+    fn random_bits(len: usize, one_in: usize) -> BitVector {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        (0..len).map(|_| rng.gen_range(0..one_in) == 0).collect()
+    }
+
+    fn runs(len: usize, run: usize) -> BitVector {
+        (0..len).map(|i| (i / run) % 2 == 1).collect()
+    }
+
+    fn test_against_bit_vector(adaptive: &AdaptiveBitVector, bit_vector: &BitVector) {
+        assert_eq!(adaptive.len(), bit_vector.len());
+        for i in (0..bit_vector.len()).step_by(13) {
+            assert_eq!(adaptive.access(i), bit_vector.access(i));
+            assert_eq!(adaptive.rank0(i), bit_vector.rank0(i));
+            assert_eq!(adaptive.rank1(i), bit_vector.rank1(i));
+        }
+        for k in (0..=bit_vector.rank1(bit_vector.len())).step_by(7) {
+            assert_eq!(adaptive.select1(k), bit_vector.select1(k));
+        }
+        for k in (0..=bit_vector.rank0(bit_vector.len())).step_by(7) {
+            assert_eq!(adaptive.select0(k), bit_vector.select0(k));
+        }
+    }
+
+    #[test]
+    fn test_dense() {
+        let bit_vector = random_bits(100000, 2);
+        let adaptive = AdaptiveBitVector::build(bit_vector.clone());
+        assert_eq!(adaptive.representation(), Representation::Rrr);
+        test_against_bit_vector(&adaptive, &bit_vector);
+    }
+
+    #[test]
+    fn test_sparse() {
+        // the constant size of the select tables only pays off for longer vectors
+        let bit_vector = random_bits(1 << 20, 10000);
+        let adaptive = AdaptiveBitVector::build(bit_vector.clone());
+        assert_eq!(adaptive.representation(), Representation::Sparse);
+        test_against_bit_vector(&adaptive, &bit_vector);
+    }
+
+    #[test]
+    fn test_runs() {
+        let bit_vector = runs(1 << 20, 50000);
+        let adaptive = AdaptiveBitVector::build(bit_vector.clone());
+        assert_eq!(adaptive.representation(), Representation::RunLength);
+        test_against_bit_vector(&adaptive, &bit_vector);
+    }
+
+    #[test]
+    fn test_budget() {
+        let bit_vector = random_bits(100000, 1000);
+        let smallest = AdaptiveBitVector::build(bit_vector.clone());
+        // a large budget allows the fastest representation
+        let fastest = AdaptiveBitVector::build_with_budget(bit_vector.clone(), usize::MAX);
+        assert_eq!(fastest.representation(), Representation::Plain);
+        test_against_bit_vector(&fastest, &bit_vector);
+        // a budget which is too small for everything chooses the smallest representation
+        let too_small = AdaptiveBitVector::build_with_budget(bit_vector.clone(), 0);
+        assert_eq!(too_small.representation(), smallest.representation());
+        let fitting = AdaptiveBitVector::build_with_budget(bit_vector, smallest.get_size());
+        assert!(fitting.get_size() <= smallest.get_size());
+    }
+
+    #[test]
+    fn test_estimates() {
+        // random and clustered bits at several densities are estimated within 10%
+        for bit_vector in [random_bits(100000, 2), random_bits(100000, 20), random_bits(1 << 20, 10000), runs(1 << 20, 50000), runs(100000, 7)] {
+            let (len, ones) = (bit_vector.len(), bit_vector.count_ones(0..bit_vector.len()));
+            let runs = bit_vector.runs().filter(|&(bit, _, _)| bit).count();
+            let sizes = [
+                (IndexedBitVector::estimate_size(len, ones), bit_vector.clone().build().get_size()),
+                (SparseBitVector::estimate_size(len, ones), SparseBitVector::from(&bit_vector).get_size()),
+                (RrrBitVector::estimate_size(len, ones, runs), RrrBitVector::from(&bit_vector).get_size()),
+                (RunLengthBitVector::estimate_size(len, ones, runs), RunLengthBitVector::from(&bit_vector).get_size()),
+            ];
+            for (estimate, size) in sizes {
+                assert!(estimate.abs_diff(size) * 10 <= size, "estimated {estimate} bytes but needs {size} bytes");
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let adaptive = AdaptiveBitVector::build(BitVector::new());
+        assert!(adaptive.is_empty());
+        assert_eq!(adaptive.rank1(0), 0);
+        assert_eq!(adaptive.select1(0), None);
+    }
+}
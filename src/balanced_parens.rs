@@ -0,0 +1,490 @@
+use std::mem::size_of;
+use crate::BitVector;
+
+/// The number of bits covered by a leaf of the range min-max tree
+const BLOCK_SIZE: usize = 512;
+
+/// A sequence of balanced parentheses representing an ordered tree (or forest) in two bits per node
+/// Every node is represented by an opening parenthesis `1`, followed by its children and a closing parenthesis `0`.
+/// Nodes are identified by the position of their opening parenthesis.
+///
+/// The excess at position `k` is the number of opening minus the number of closing parentheses in `0..k`.
+/// Navigation is reduced to searching the next or previous position with a given excess.
+/// To search quickly, a range min-max tree stores the minimum and maximum excess of every block of `BLOCK_SIZE` bits
+/// and of every node of a complete binary tree over the blocks.
+/// As the excess changes by one with every bit, a node contains a given excess iff it is between its minimum and maximum.
+pub struct BalancedParens {
+    /// The parentheses with rank and select structures
+    bits: BitVector,
+    /// The number of leaves of the range min-max tree, a power of two
+    leaves: usize,
+    /// The minimum excess of every node relative to the excess at its start, the root at index 1
+    min: Vec<i32>,
+    /// The maximum excess of every node relative to the excess at its start, the root at index 1
+    max: Vec<i32>,
+}
+
+impl BalancedParens {
+    /// Creates balanced parentheses from `bits` where `1` is an opening and `0` a closing parenthesis
+    /// Panics if the parentheses are not balanced.
+    pub fn new(mut bits: BitVector) -> Self {
+        bits.init();
+        let num_blocks = bits.len().div_ceil(BLOCK_SIZE);
+        let leaves = num_blocks.next_power_of_two();
+        // unused leaves contain no excess at all
+        let mut min = vec![i32::MAX; 2 * leaves];
+        let mut max = vec![i32::MIN; 2 * leaves];
+        let mut excess = vec![0; 2 * leaves];
+        let mut total = 0;
+        for block in 0..num_blocks {
+            let node = leaves + block;
+            for i in block * BLOCK_SIZE..bits.len().min((block + 1) * BLOCK_SIZE) {
+                excess[node] += if bits.access(i) == 1 { 1 } else { -1 };
+                min[node] = min[node].min(excess[node]);
+                max[node] = max[node].max(excess[node]);
+            }
+            total += excess[node] as isize;
+            assert!(total + (min[node] - excess[node]) as isize >= 0, "The parentheses are not balanced");
+        }
+        assert_eq!(total, 0, "The parentheses are not balanced");
+        for node in (1..leaves).rev() {
+            let (left, right) = (2 * node, 2 * node + 1);
+            excess[node] = excess[left] + excess[right];
+            min[node] = min[left].min(excess[left].saturating_add(min[right]));
+            max[node] = max[left].max(excess[left].saturating_add(max[right]));
+        }
+        BalancedParens {
+            bits,
+            leaves,
+            min,
+            max,
+        }
+    }
+
+    /// Creates balanced parentheses from a string of `(` and `)`
+    /// Panics if the string contains other characters or the parentheses are not balanced.
+    pub fn load_from_string(data: &str) -> Self {
+        Self::new(data.chars().map(|c| match c {
+            '(' => true,
+            ')' => false,
+            _ => panic!("Invalid parenthesis {c}"),
+        }).collect())
+    }
+
+    /// Get the size of the parentheses including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<BalancedParens>() + self.bits.get_size()
+        + (self.min.capacity() + self.max.capacity()) * size_of::<i32>()
+    }
+
+    /// Get the parentheses as bit vector
+    #[inline]
+    pub fn as_bit_vector(&self) -> &BitVector {
+        &self.bits
+    }
+
+    /// Get the number of parentheses
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Whether there are no parentheses
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Get the number of nodes
+    #[inline]
+    pub fn num_nodes(&self) -> usize {
+        self.len() / 2
+    }
+
+    /// Whether the parenthesis at `index` is an opening one
+    #[inline]
+    pub fn is_open(&self, index: usize) -> bool {
+        self.bits.access(index) == 1
+    }
+
+    /// Get the excess at `index`, i.e. the number of opening minus the number of closing parentheses in `0..index`
+    #[inline]
+    pub fn excess(&self, index: usize) -> isize {
+        2 * self.bits.rank1(index) as isize - index as isize
+    }
+
+    /// Get the position of the first bit of the blocks below `node`
+    #[inline]
+    fn node_start(&self, node: usize) -> usize {
+        let height = self.leaves.ilog2() - node.ilog2();
+        ((node << height) - self.leaves) * BLOCK_SIZE
+    }
+
+    /// Whether the excess `target` occurs in `node`
+    #[inline]
+    fn contains(&self, node: usize, target: isize) -> bool {
+        // unused nodes have a minimum above their maximum
+        if self.min[node] > self.max[node] {
+            return false;
+        }
+        let relative = target - self.excess(self.node_start(node));
+        self.min[node] as isize <= relative && relative <= self.max[node] as isize
+    }
+
+    /// Iterate over the positions `from..=to` together with their excess
+    fn scan(&self, from: usize, to: usize) -> impl Iterator<Item = (usize, isize)> + '_ {
+        let mut excess = self.excess(from);
+        std::iter::once((from, excess)).chain((from..to).map(move |i| {
+            excess += if self.is_open(i) { 1 } else { -1 };
+            (i + 1, excess)
+        }))
+    }
+
+    /// Get the position after the last bit of `block`
+    #[inline]
+    fn block_end(&self, block: usize) -> usize {
+        self.len().min((block + 1) * BLOCK_SIZE)
+    }
+
+    /// Get the smallest position `k >= from` with an excess of `target`
+    pub fn fwd_search(&self, from: usize, target: isize) -> Option<usize> {
+        if from > self.len() {
+            return None;
+        }
+        // scan the rest of the block
+        let block = from / BLOCK_SIZE;
+        let end = self.block_end(block);
+        if let Some((k, _)) = self.scan(from, end).find(|&(_, excess)| excess == target) {
+            return Some(k);
+        }
+        if end == self.len() {
+            return None;
+        }
+        // find the next node to the right containing the excess
+        let mut node = self.leaves + block;
+        loop {
+            if node == 1 {
+                return None;
+            }
+            if node.is_multiple_of(2) && self.contains(node + 1, target) {
+                node += 1;
+                break;
+            }
+            node /= 2;
+        }
+        // descend to its leftmost leaf containing the excess
+        while node < self.leaves {
+            node = if self.contains(2 * node, target) { 2 * node } else { 2 * node + 1 };
+        }
+        let block = node - self.leaves;
+        self.scan(block * BLOCK_SIZE, self.block_end(block)).find(|&(_, excess)| excess == target).map(|(k, _)| k)
+    }
+
+    /// Get the largest position `k <= from` with an excess of `target`
+    pub fn bwd_search(&self, from: usize, target: isize) -> Option<usize> {
+        let from = from.min(self.len());
+        // the position before the first bit is not covered by any block
+        let at_start = (target == 0).then_some(0);
+        if from == 0 {
+            return at_start;
+        }
+        // scan the start of the block
+        let block = (from - 1) / BLOCK_SIZE;
+        if let Some((k, _)) = self.scan(block * BLOCK_SIZE, from).filter(|&(_, excess)| excess == target).last() {
+            return Some(k);
+        }
+        // find the next node to the left containing the excess
+        let mut node = self.leaves + block;
+        loop {
+            if node == 1 {
+                return at_start;
+            }
+            if !node.is_multiple_of(2) && self.contains(node - 1, target) {
+                node -= 1;
+                break;
+            }
+            node /= 2;
+        }
+        // descend to its rightmost leaf containing the excess
+        while node < self.leaves {
+            node = if self.contains(2 * node + 1, target) { 2 * node + 1 } else { 2 * node };
+        }
+        let block = node - self.leaves;
+        self.scan(block * BLOCK_SIZE, self.block_end(block)).filter(|&(_, excess)| excess == target).last().map(|(k, _)| k)
+    }
+
+    /// Get the minimum excess of the positions `from..=to`
+    pub(crate) fn min_excess(&self, from: usize, to: usize) -> isize {
+        let first_block = from.div_ceil(BLOCK_SIZE);
+        let last_block = to / BLOCK_SIZE;
+        if first_block >= last_block {
+            return self.scan(from, to).map(|(_, excess)| excess).min().unwrap();
+        }
+        // the partial blocks at both ends are scanned, the full blocks in between use the tree
+        let mut min = self.scan(from, first_block * BLOCK_SIZE).chain(self.scan(last_block * BLOCK_SIZE, to))
+            .map(|(_, excess)| excess).min().unwrap();
+        let mut left = self.leaves + first_block;
+        let mut right = self.leaves + last_block;
+        while left < right {
+            if !left.is_multiple_of(2) {
+                min = min.min(self.excess(self.node_start(left)) + self.min[left] as isize);
+                left += 1;
+            }
+            if !right.is_multiple_of(2) {
+                right -= 1;
+                min = min.min(self.excess(self.node_start(right)) + self.min[right] as isize);
+            }
+            left /= 2;
+            right /= 2;
+        }
+        min
+    }
+
+    /// Get the position of the closing parenthesis matching the opening one at `index`
+    pub fn find_close(&self, index: usize) -> usize {
+        assert!(self.is_open(index), "No opening parenthesis at {index}");
+        self.fwd_search(index + 1, self.excess(index)).expect("The parentheses are not balanced") - 1
+    }
+
+    /// Get the position of the opening parenthesis matching the closing one at `index`
+    pub fn find_open(&self, index: usize) -> usize {
+        assert!(!self.is_open(index), "No closing parenthesis at {index}");
+        self.bwd_search(index, self.excess(index + 1)).expect("The parentheses are not balanced")
+    }
+
+    /// Get the opening parenthesis of the closest pair enclosing the opening one at `index`
+    /// Returns `None` if the pair is not enclosed.
+    pub fn enclose(&self, index: usize) -> Option<usize> {
+        assert!(self.is_open(index), "No opening parenthesis at {index}");
+        let excess = self.excess(index);
+        if excess == 0 {
+            return None;
+        }
+        self.bwd_search(index, excess - 1)
+    }
+
+    /// Get the id of `node` in preorder, counting from zero
+    #[inline]
+    pub fn node_id(&self, node: usize) -> usize {
+        self.bits.rank1(node)
+    }
+
+    /// Get the node with preorder id `id`
+    /// Returns `None` if there are at most `id` nodes.
+    #[inline]
+    pub fn node(&self, id: usize) -> Option<usize> {
+        self.bits.select1(id)
+    }
+
+    /// Get the parent of `node`
+    /// Returns `None` if `node` is a root.
+    #[inline]
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.enclose(node)
+    }
+
+    /// Get the first child of `node`
+    /// Returns `None` if `node` is a leaf.
+    #[inline]
+    pub fn first_child(&self, node: usize) -> Option<usize> {
+        assert!(self.is_open(node), "No opening parenthesis at {node}");
+        (self.is_open(node + 1)).then_some(node + 1)
+    }
+
+    /// Get the next sibling of `node`
+    /// Returns `None` if `node` is the last child of its parent.
+    #[inline]
+    pub fn next_sibling(&self, node: usize) -> Option<usize> {
+        let sibling = self.find_close(node) + 1;
+        (sibling < self.len() && self.is_open(sibling)).then_some(sibling)
+    }
+
+    /// Get the number of nodes in the subtree of `node` including `node`
+    #[inline]
+    pub fn subtree_size(&self, node: usize) -> usize {
+        (self.find_close(node) - node).div_ceil(2)
+    }
+
+    /// Get the depth of `node`, the roots have depth zero
+    #[inline]
+    pub fn depth(&self, node: usize) -> usize {
+        assert!(self.is_open(node), "No opening parenthesis at {node}");
+        self.excess(node) as usize
+    }
+
+    /// Whether `ancestor` is an ancestor of `node` or `node` itself
+    #[inline]
+    pub fn is_ancestor(&self, ancestor: usize, node: usize) -> bool {
+        ancestor <= node && node < self.find_close(ancestor)
+    }
+
+    /// Get the lowest common ancestor of `u` and `v`
+    /// Returns `None` if the nodes are in different trees of a forest.
+    pub fn lca(&self, u: usize, v: usize) -> Option<usize> {
+        let (u, v) = (u.min(v), u.max(v));
+        if self.is_ancestor(u, v) {
+            return Some(u);
+        }
+        // between u and v the excess drops to one more than the depth of the lowest common ancestor
+        let min = self.min_excess(u + 1, v);
+        if min == 0 {
+            return None;
+        }
+        self.bwd_search(u, min - 1)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::BalancedParens;
+
+    /// A pointer based forest together with the positions of its nodes in the parentheses
+    struct Reference {
+        parents: Vec<Option<usize>>,
+        children: Vec<Vec<usize>>,
+        positions: Vec<usize>,
+        preorder: Vec<usize>,
+        parens: String,
+    }
+
+    impl Reference {
+        /// Creates a random forest with `roots` trees
+        fn random(nodes: usize, roots: usize, rng: &mut ChaCha8Rng) -> Self {
+            let parents: Vec<Option<usize>> = (0..nodes).map(|i| {
+                if i < roots { None } else { Some(rng.gen_range(0..i)) }
+            }).collect();
+            let mut children = vec![Vec::new(); nodes];
+            for (i, parent) in parents.iter().enumerate() {
+                if let Some(parent) = parent {
+                    children[*parent].push(i);
+                }
+            }
+            let mut reference = Reference { parents, children, positions: vec![0; nodes], preorder: Vec::new(), parens: String::new() };
+            for root in 0..roots {
+                reference.write(root);
+            }
+            reference
+        }
+
+        fn write(&mut self, node: usize) {
+            self.positions[node] = self.parens.len();
+            self.preorder.push(node);
+            self.parens.push('(');
+            for child in self.children[node].clone() {
+                self.write(child);
+            }
+            self.parens.push(')');
+        }
+
+        fn depth(&self, node: usize) -> usize {
+            self.parents[node].map_or(0, |parent| self.depth(parent) + 1)
+        }
+
+        fn subtree_size(&self, node: usize) -> usize {
+            1 + self.children[node].iter().map(|&child| self.subtree_size(child)).sum::<usize>()
+        }
+
+        fn ancestors(&self, node: usize) -> Vec<usize> {
+            let mut ancestors = vec![node];
+            while let Some(parent) = self.parents[*ancestors.last().unwrap()] {
+                ancestors.push(parent);
+            }
+            ancestors
+        }
+
+        fn next_sibling(&self, node: usize) -> Option<usize> {
+            let siblings = &self.children[self.parents[node]?];
+            let i = siblings.iter().position(|&sibling| sibling == node).unwrap();
+            siblings.get(i + 1).copied()
+        }
+    }
+
+    fn test_against_reference(reference: &Reference, rng: &mut ChaCha8Rng) {
+        let parens = BalancedParens::load_from_string(&reference.parens);
+        let node = |id: usize| reference.positions[id];
+        assert_eq!(parens.num_nodes(), reference.parents.len());
+        for (rank, &id) in reference.preorder.iter().enumerate() {
+            let position = node(id);
+            assert_eq!(parens.node(rank), Some(position));
+            assert_eq!(parens.node_id(position), rank);
+            let close = parens.find_close(position);
+            assert_eq!(parens.find_open(close), position);
+            assert_eq!(parens.parent(position), reference.parents[id].map(node));
+            assert_eq!(parens.first_child(position), reference.children[id].first().map(|&child| node(child)));
+            assert_eq!(parens.subtree_size(position), reference.subtree_size(id));
+            assert_eq!(parens.depth(position), reference.depth(id));
+            // the next sibling of a root is the next root, which is not a sibling in the reference
+            if reference.parents[id].is_some() {
+                assert_eq!(parens.next_sibling(position), reference.next_sibling(id).map(node));
+            }
+        }
+        assert_eq!(parens.node(reference.parents.len()), None);
+        for _ in 0..1000.min(reference.parents.len() * reference.parents.len()) {
+            let u = rng.gen_range(0..reference.parents.len());
+            let v = rng.gen_range(0..reference.parents.len());
+            let ancestors = reference.ancestors(u);
+            let lca = reference.ancestors(v).into_iter().find(|ancestor| ancestors.contains(ancestor));
+            assert_eq!(parens.lca(node(u), node(v)), lca.map(node));
+        }
+    }
+
+    #[test]
+    fn test_random_trees() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for nodes in [1, 2, 10, 300, 5000] {
+            let reference = Reference::random(nodes, 1, &mut rng);
+            test_against_reference(&reference, &mut rng);
+        }
+    }
+
+    #[test]
+    fn test_forest() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let reference = Reference::random(3000, 20, &mut rng);
+        test_against_reference(&reference, &mut rng);
+    }
+
+    #[test]
+    fn test_deep_path() {
+        // a path needs searches through many blocks
+        let nodes = 3000;
+        let parens = BalancedParens::load_from_string(&("(".repeat(nodes) + &")".repeat(nodes)));
+        assert_eq!(parens.find_close(0), 2 * nodes - 1);
+        assert_eq!(parens.find_open(2 * nodes - 1), 0);
+        assert_eq!(parens.depth(nodes - 1), nodes - 1);
+        assert_eq!(parens.parent(nodes - 1), Some(nodes - 2));
+        assert_eq!(parens.subtree_size(0), nodes);
+        assert_eq!(parens.lca(nodes - 1, 10), Some(10));
+    }
+
+    #[test]
+    fn test_search() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let reference = Reference::random(2000, 3, &mut rng);
+        let parens = BalancedParens::load_from_string(&reference.parens);
+        let excess: Vec<isize> = (0..=parens.len()).map(|k| parens.excess(k)).collect();
+        for _ in 0..2000 {
+            let from = rng.gen_range(0..=parens.len());
+            let target = rng.gen_range(-1..60);
+            assert_eq!(parens.fwd_search(from, target), (from..=parens.len()).find(|&k| excess[k] == target));
+            assert_eq!(parens.bwd_search(from, target), (0..=from).rev().find(|&k| excess[k] == target));
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let parens = BalancedParens::load_from_string("");
+        assert!(parens.is_empty());
+        assert_eq!(parens.node(0), None);
+        assert_eq!(parens.fwd_search(0, 0), Some(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unbalanced() {
+        BalancedParens::load_from_string("())(");
+    }
+}
@@ -57,7 +57,7 @@ fn main() {
 
         let start = Instant::now();
         for command in commands {
-            vector.rank(command.0, command.1);
+            if command.0 { vector.rank1(command.1) } else { vector.rank0(command.1) };
         }
         let end = Instant::now();
         let t = (end - start).as_secs_f64();
@@ -0,0 +1,69 @@
+use std::fs::File;
+use std::io::Write;
+use std::ops::Range;
+use std::time::Instant;
+use chrono::Local;
+use rand_chacha::ChaCha8Rng;
+use rand::Rng;
+use rand::SeedableRng;
+use bit_vector::{BitVector, RrrBitVector};
+
+const POINTS: usize = 28;
+const ITERATIONS: usize = 1000000;
+/// The maximum length of a run of equal bits
+const MAX_RUN: usize = 256;
+
+/// Generates a clustered bit vector consisting of runs of random length
+fn generate_bit_vector(len: usize) -> BitVector {
+    let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+    let mut bit = false;
+    let mut run = 0;
+    (0..len).map(|_| {
+        if run == 0 {
+            run = rng.gen_range(1..=MAX_RUN);
+            bit = !bit;
+        }
+        run -= 1;
+        bit
+    }).collect()
+}
+
+fn generate_queries(num: usize, range: Range<usize>) -> Vec<usize> {
+    let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+    (0..num).map(|_| rng.gen_range(range.clone())).collect()
+}
+
+fn main() {
+    let mut out = format!("% rrr benchmark {} points: {POINTS} iterations: {ITERATIONS} max run: {MAX_RUN}\nbits buildT rankR selectR rrrS plainS\n", Local::now().format("%d/%m/%Y %H:%M"));
+
+    for i in 1..POINTS {
+        let mut vector = generate_bit_vector(1usize << i);
+
+        let start = Instant::now();
+        let rrr = RrrBitVector::from(&vector);
+        let end = Instant::now();
+        let build = (end - start).as_secs_f64();
+
+        let commands = generate_queries(ITERATIONS, 0..(1usize << i));
+        let start = Instant::now();
+        for command in commands {
+            rrr.rank1(command);
+        }
+        let end = Instant::now();
+        let rank = ITERATIONS as f64 / (end - start).as_secs_f64();
+
+        let commands = generate_queries(ITERATIONS, 0..rrr.count_ones());
+        let start = Instant::now();
+        for command in commands {
+            rrr.select1(command);
+        }
+        let end = Instant::now();
+        let select = ITERATIONS as f64 / (end - start).as_secs_f64();
+
+        // compare against the plain bit vector including its accelerators
+        vector.init();
+        out += &format!("{} {} {} {} {} {}\n", 1usize << i, build, rank, select, rrr.get_size(), vector.get_size());
+    }
+    let mut file = File::create("./rrr_benchmark.tex").unwrap();
+    file.write_all(out.as_bytes()).unwrap();
+}
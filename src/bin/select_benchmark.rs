@@ -59,11 +59,11 @@ fn main() {
         vector.init_select_structures();
         let ones = vector.count_ones(0..(1usize << i));
   
-        let commands = generate_select_queries(ITERATIONS, 1..((1usize << i) - ones + 1), 1..(ones+1));
+        let commands = generate_select_queries(ITERATIONS, 0..((1usize << i) - ones), 0..ones);
 
         let start = Instant::now();
         for command in commands {
-            vector.select(command.0, command.1);
+            if command.0 { vector.select1(command.1) } else { vector.select0(command.1) };
         }
         let end = Instant::now();
         let t = (end - start).as_secs_f64();
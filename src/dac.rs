@@ -0,0 +1,185 @@
+use std::mem::size_of;
+use crate::{BitVector, IntVector};
+
+/// The default number of bits of every chunk
+const DEFAULT_CHUNK_WIDTH: usize = 8;
+
+/// A vector of integers using directly addressable codes (DAC)
+/// Every integer is split into chunks of `chunk_width` bits, starting with the least significant chunk.
+/// Level `l` stores the `l`-th chunk of every integer with more than `l` chunks in an `IntVector`
+/// and a `BitVector` telling whether the integer continues on the next level.
+/// The position of an integer on the next level is the rank of its continuation bit,
+/// so small integers only need few bits while random access is still possible.
+#[derive(Clone)]
+pub struct DacVector {
+    /// The number of integers
+    len: usize,
+    /// The number of bits of every chunk
+    chunk_width: usize,
+    /// The chunks of every level
+    chunks: Vec<IntVector>,
+    /// Whether the integer continues on the next level, for all levels except the last one
+    continues: Vec<BitVector>,
+}
+
+impl FromIterator<u64> for DacVector {
+    /// Creates a DAC vector of the values of `iter` with the default chunk width
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        Self::new(&iter.into_iter().collect::<Vec<u64>>())
+    }
+}
+
+impl From<&[u64]> for DacVector {
+    /// Creates a DAC vector of `values` with the default chunk width
+    fn from(values: &[u64]) -> Self {
+        Self::new(values)
+    }
+}
+
+impl DacVector {
+    /// Creates a DAC vector of `values` with the default chunk width
+    pub fn new(values: &[u64]) -> Self {
+        Self::with_chunk_width(values, DEFAULT_CHUNK_WIDTH)
+    }
+
+    /// Creates a DAC vector of `values` with chunks of `chunk_width` bits
+    pub fn with_chunk_width(values: &[u64], chunk_width: usize) -> Self {
+        assert!((1..=u64::BITS as usize).contains(&chunk_width), "chunk width {chunk_width} is not in 1..=64");
+        let mask = if chunk_width == u64::BITS as usize { u64::MAX } else { (1 << chunk_width) - 1 };
+        let mut chunks = Vec::new();
+        let mut continues = Vec::new();
+        // the remaining parts of the integers which continue on the current level
+        let mut remaining = values.to_vec();
+        loop {
+            chunks.push(IntVector::from_slice_with_width(&remaining.iter().map(|value| value & mask).collect::<Vec<u64>>(), chunk_width));
+            let next: Vec<u64> = remaining.iter().filter_map(|value| value.checked_shr(chunk_width as u32).filter(|&rest| rest > 0)).collect();
+            if next.is_empty() {
+                break;
+            }
+            let mut bit_vector: BitVector = remaining.iter().map(|value| value.checked_shr(chunk_width as u32).is_some_and(|rest| rest > 0)).collect();
+            bit_vector.init_rank_structures();
+            continues.push(bit_vector);
+            remaining = next;
+        }
+        DacVector {
+            len: values.len(),
+            chunk_width,
+            chunks,
+            continues,
+        }
+    }
+
+    /// Get the size of the DAC vector including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<DacVector>()
+        + self.chunks.iter().map(|chunks| chunks.get_size()).sum::<usize>()
+        + self.continues.iter().map(|continues| size_of::<BitVector>() + continues.get_size()).sum::<usize>()
+    }
+
+    /// Get the number of integers
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no integers
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of bits of every chunk
+    #[inline]
+    pub fn chunk_width(&self) -> usize {
+        self.chunk_width
+    }
+
+    /// Get the number of levels, i.e. the number of chunks of the largest integer
+    #[inline]
+    pub fn num_levels(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Get the integer at `index`
+    pub fn get(&self, index: usize) -> u64 {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        let mut index = index;
+        let mut value = 0;
+        for level in 0..self.num_levels() {
+            value |= self.chunks[level].get(index) << (level * self.chunk_width);
+            if level == self.continues.len() || self.continues[level].access(index) == 0 {
+                break;
+            }
+            index = self.continues[level].rank1(index);
+        }
+        value
+    }
+
+    /// Iterate over the integers
+    /// The position on every level is only advanced, so no rank queries are needed.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        let mut positions = vec![0; self.num_levels()];
+        (0..self.len).map(move |_| {
+            let mut value = 0;
+            for (level, position) in positions.iter_mut().enumerate() {
+                let index = *position;
+                *position += 1;
+                value |= self.chunks[level].get(index) << (level * self.chunk_width);
+                if level == self.continues.len() || self.continues[level].access(index) == 0 {
+                    break;
+                }
+            }
+            value
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::{DacVector, IntVector};
+
+    /// Generates mostly small values with rare large ones
+    fn skewed_values(len: usize) -> Vec<u64> {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        (0..len).map(|_| match rng.gen_range(0..100) {
+            0 => rng.gen::<u64>(),
+            1..=5 => rng.gen_range(0..1 << 20),
+            _ => rng.gen_range(0..200),
+        }).collect()
+    }
+
+    #[test]
+    fn test_chunk_widths() {
+        let values = skewed_values(5000);
+        for chunk_width in [1, 3, 7, 8, 16, 63, 64] {
+            let dac = DacVector::with_chunk_width(&values, chunk_width);
+            assert_eq!(dac.len(), values.len());
+            assert_eq!(dac.num_levels(), 64usize.div_ceil(chunk_width));
+            for (i, &value) in values.iter().enumerate() {
+                assert_eq!(dac.get(i), value);
+            }
+            assert_eq!(dac.iter().collect::<Vec<u64>>(), values);
+        }
+    }
+
+    #[test]
+    fn test_size() {
+        let values = skewed_values(100000);
+        let dac = DacVector::new(&values);
+        assert!(dac.get_size() * 3 < IntVector::from_slice(&values).get_size());
+    }
+
+    #[test]
+    fn test_small() {
+        let dac: DacVector = [0, 255, 256, 65535, 65536].into_iter().collect();
+        assert_eq!(dac.num_levels(), 3);
+        assert_eq!(dac.iter().collect::<Vec<u64>>(), vec![0, 255, 256, 65535, 65536]);
+        let empty = DacVector::new(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.iter().count(), 0);
+        assert_eq!(DacVector::from(&[0u64, 0][..]).num_levels(), 1);
+    }
+}
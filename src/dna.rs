@@ -0,0 +1,288 @@
+use std::error::Error;
+use std::fmt;
+use std::mem::size_of;
+
+/// The bases in the order of their 2-bit codes
+const BASES: [u8; 4] = [b'A', b'C', b'G', b'T'];
+
+/// The number of words storing bases in a block
+const WORDS_PER_BLOCK: usize = 7;
+
+/// The number of bases in a word
+const BASES_PER_WORD: usize = u64::BITS as usize / 2;
+
+/// The number of bases in a block
+const BLOCK_SIZE: usize = WORDS_PER_BLOCK * BASES_PER_WORD;
+
+/// The number of blocks in a super block
+/// A super block has 57344 bases, so the counts inside it fit into `u16`.
+const BLOCKS_PER_SUPER_BLOCK: usize = 256;
+
+/// Every second bit of a word, i.e. the lower bit of every base
+const LOWER_BITS: u64 = 0x5555_5555_5555_5555;
+
+/// A block of bases together with the counts of all bases before it
+/// Like the blocks of `RankAccelerator` the counts start at the beginning of the super block.
+/// The block fills exactly one cache line, so `rank` reads only one cache line besides the super block counts.
+#[derive(Clone, Default)]
+#[repr(C, align(64))]
+struct Block {
+    /// The number of every base from the start of the super block to the start of the block
+    counts: [u16; 4],
+    /// The packed bases, two bits each, starting with the least significant bits
+    words: [u64; WORDS_PER_BLOCK],
+}
+
+/// A DNA sequence over the bases A, C, G and T supporting rank for every base
+/// The bases are packed into two bits each and interleaved with the counts of the four bases,
+/// so the counts and the bases needed by a query are in the same cache line.
+/// Super blocks store the absolute counts, blocks the counts relative to their super block.
+#[derive(Clone)]
+pub struct DnaRank {
+    /// The number of bases
+    len: usize,
+    /// The blocks of bases with their relative counts
+    blocks: Vec<Block>,
+    /// The number of every base before the start of the super block
+    super_blocks: Vec<[usize; 4]>,
+}
+
+/// The error returned when a sequence contains a character other than A, C, G or T
+/// `DnaRank` stores exactly two bits per base, so `N` and the other IUPAC codes are rejected instead of being mapped to one of the bases.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidBase {
+    /// The invalid character
+    pub base: u8,
+    /// The index of the character in the sequence of its record
+    pub index: usize,
+    /// The line of the character counting from one, if the sequence was read from FASTA text
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for InvalidBase {
+    /// Names the invalid base and its position
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid base {:?} at index {}", self.base as char, self.index)?;
+        if let Some(line) = self.line {
+            write!(f, " in line {line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for InvalidBase {}
+
+/// Get the 2-bit code of the ASCII `base`, ignoring case
+/// Returns `None` if `base` is not one of A, C, G or T.
+#[inline]
+fn encode(base: u8) -> Option<u64> {
+    match base.to_ascii_uppercase() {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    }
+}
+
+/// Count the bases with `code` in the first `len` bases of `word`
+#[inline]
+fn count_in_word(word: u64, code: u64, len: usize) -> usize {
+    // bases equal to `code` become 00, the lower bit of every such base is set
+    let diff = word ^ (code * LOWER_BITS);
+    let matches = !(diff | (diff >> 1)) & LOWER_BITS;
+    let mask = if len == BASES_PER_WORD { u64::MAX } else { (1 << (2 * len)) - 1 };
+    (matches & mask).count_ones() as usize
+}
+
+impl DnaRank {
+    /// Creates a DNA sequence of the ASCII `bases`
+    /// Lower case bases are accepted, any other character returns an error.
+    pub fn new(bases: &[u8]) -> Result<Self, InvalidBase> {
+        // there is a block for the end of the sequence even if it is the start of a new block, so `rank` never has to check it
+        let mut blocks = vec![Block::default(); bases.len() / BLOCK_SIZE + 1];
+        let mut super_blocks = Vec::with_capacity(blocks.len().div_ceil(BLOCKS_PER_SUPER_BLOCK));
+        let mut counts = [0; 4];
+        let mut super_block_counts = [0; 4];
+        for (i, block) in blocks.iter_mut().enumerate() {
+            if i.is_multiple_of(BLOCKS_PER_SUPER_BLOCK) {
+                super_blocks.push(counts);
+                super_block_counts = counts;
+            }
+            for code in 0..4 {
+                block.counts[code] = (counts[code] - super_block_counts[code]) as u16;
+            }
+            let end = bases.len().min((i + 1) * BLOCK_SIZE);
+            for (j, &base) in bases[i * BLOCK_SIZE..end].iter().enumerate() {
+                let code = encode(base).ok_or(InvalidBase { base, index: i * BLOCK_SIZE + j, line: None })?;
+                block.words[j / BASES_PER_WORD] |= code << (2 * (j % BASES_PER_WORD));
+                counts[code as usize] += 1;
+            }
+        }
+        Ok(DnaRank {
+            len: bases.len(),
+            blocks,
+            super_blocks,
+        })
+    }
+
+    /// Creates one DNA sequence for every record of the `fasta` text together with the header of the record
+    /// Lines starting with `>` start a new record, lines starting with `;` are comments.
+    /// Bases without a preceding header form a record with an empty header.
+    /// Returns an error for the first character of a sequence line which is not one of A, C, G or T.
+    pub fn from_fasta(fasta: &str) -> Result<Vec<(String, DnaRank)>, InvalidBase> {
+        let mut records = Vec::new();
+        let mut header = None;
+        let mut bases = Vec::new();
+        for (line_index, line) in fasta.lines().map(str::trim).enumerate() {
+            if let Some(name) = line.strip_prefix('>') {
+                if header.is_some() || !bases.is_empty() {
+                    records.push((header.take().unwrap_or_default(), DnaRank::new(&bases)?));
+                    bases.clear();
+                }
+                header = Some(name.trim().to_string());
+            } else if !line.starts_with(';') {
+                // check the line here to report where the invalid base is
+                if let Some(column) = line.bytes().position(|base| encode(base).is_none()) {
+                    return Err(InvalidBase { base: line.as_bytes()[column], index: bases.len() + column, line: Some(line_index + 1) });
+                }
+                bases.extend_from_slice(line.as_bytes());
+            }
+        }
+        if header.is_some() || !bases.is_empty() {
+            records.push((header.unwrap_or_default(), DnaRank::new(&bases)?));
+        }
+        Ok(records)
+    }
+
+    /// Get the size of the DNA sequence including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<DnaRank>()
+        + self.blocks.capacity() * size_of::<Block>()
+        + self.super_blocks.capacity() * size_of::<[usize; 4]>()
+    }
+
+    /// Get the number of bases
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no bases
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the ASCII base at `index`
+    #[inline]
+    pub fn access(&self, index: usize) -> u8 {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        let offset = index % BLOCK_SIZE;
+        let word = self.blocks[index / BLOCK_SIZE].words[offset / BASES_PER_WORD];
+        BASES[((word >> (2 * (offset % BASES_PER_WORD))) & 3) as usize]
+    }
+
+    /// Get the number of occurrences of the ASCII `base` before `index`, i.e. in `0..index`
+    /// `index` may be equal to the number of bases.
+    /// Panics if `base` is not one of A, C, G or T in upper or lower case.
+    #[inline]
+    pub fn rank(&self, base: u8, index: usize) -> usize {
+        assert!(index <= self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        let code = encode(base).unwrap_or_else(|| panic!("Invalid base {:?}", base as char));
+        let block_index = index / BLOCK_SIZE;
+        let offset = index % BLOCK_SIZE;
+        let block = &self.blocks[block_index];
+        let mut result = self.super_blocks[block_index / BLOCKS_PER_SUPER_BLOCK][code as usize] + block.counts[code as usize] as usize;
+        for &word in &block.words[..offset / BASES_PER_WORD] {
+            result += count_in_word(word, code, BASES_PER_WORD);
+        }
+        if !offset.is_multiple_of(BASES_PER_WORD) {
+            result += count_in_word(block.words[offset / BASES_PER_WORD], code, offset % BASES_PER_WORD);
+        }
+        result
+    }
+
+    /// Get the number of occurrences of every base before `index` in the order A, C, G, T
+    pub fn ranks(&self, index: usize) -> [usize; 4] {
+        BASES.map(|base| self.rank(base, index))
+    }
+
+    /// Iterate over the ASCII bases
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len).map(|i| self.access(i))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::mem::size_of;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::dna::{Block, BLOCK_SIZE};
+    use crate::{DnaRank, InvalidBase};
+
+    #[test]
+    fn test_block_is_cache_line() {
+        assert_eq!(size_of::<Block>(), 64);
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        // more than one super block and a partial last block
+        let bases: Vec<u8> = (0..60000).map(|_| b"ACGT"[rng.gen_range(0..4)]).collect();
+        let dna = DnaRank::new(&bases).unwrap();
+        assert_eq!(dna.len(), bases.len());
+        assert_eq!(dna.iter().collect::<Vec<u8>>(), bases);
+        let mut counts = [0; 4];
+        for (i, &base) in bases.iter().enumerate() {
+            if i % 7 == 0 || i % BLOCK_SIZE < 2 {
+                assert_eq!(dna.ranks(i), counts, "index {i}");
+            }
+            counts[b"ACGT".iter().position(|&b| b == base).unwrap()] += 1;
+        }
+        assert_eq!(dna.ranks(bases.len()), counts);
+    }
+
+    #[test]
+    fn test_block_boundaries() {
+        for len in [0, 1, 31, 32, 33, BLOCK_SIZE - 1, BLOCK_SIZE, BLOCK_SIZE + 1, 2 * BLOCK_SIZE] {
+            let bases: Vec<u8> = b"GATTACA".iter().cycle().take(len).copied().collect();
+            let dna = DnaRank::new(&bases).unwrap();
+            for index in 0..=len {
+                for base in *b"ACGT" {
+                    assert_eq!(dna.rank(base, index), bases[..index].iter().filter(|&&b| b == base).count());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fasta() {
+        let fasta = ">chr1 first\nACGTac\ngt\n; comment\n>chr2\n\nTTTT\n";
+        let records = DnaRank::from_fasta(fasta).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].0, "chr1 first");
+        assert_eq!(records[0].1.iter().collect::<Vec<u8>>(), b"ACGTACGT");
+        assert_eq!(records[1].0, "chr2");
+        assert_eq!(records[1].1.rank(b'T', 4), 4);
+        assert_eq!(records[1].1.rank(b'a', 4), 0);
+        assert_eq!(DnaRank::from_fasta("ACGT").unwrap()[0].0, "");
+    }
+
+    #[test]
+    fn test_invalid_base() {
+        assert_eq!(DnaRank::new(b"ACGN").err(), Some(InvalidBase { base: b'N', index: 3, line: None }));
+        let error = DnaRank::from_fasta(">chr1\nACGT\n>chr2\nAC\nGTRA\n").err().unwrap();
+        assert_eq!(error, InvalidBase { base: b'R', index: 4, line: Some(5) });
+        assert_eq!(error.to_string(), "Invalid base 'R' at index 4 in line 5");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rank_invalid_base() {
+        DnaRank::new(b"ACGT").unwrap().rank(b'N', 2);
+    }
+}
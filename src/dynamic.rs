@@ -0,0 +1,626 @@
+use std::cmp::max;
+use std::mem::size_of;
+use crate::BitVector;
+
+/// The maximum number of bits of a node
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// The minimum number of bits of a node, smaller nodes are merged with a neighbour
+const MIN_BITS: usize = WORD_BITS / 4;
+
+/// A node of the tree storing up to one word of bits
+/// The subtree fields contain the values of the node together with both children.
+struct Node {
+    /// The bits of the node, the first bit is the least significant one
+    bits: u64,
+    /// The number of bits of the node
+    len: usize,
+    /// The number of bits in the subtree
+    size: usize,
+    /// The number of ones in the subtree
+    ones: usize,
+    /// The height of the subtree
+    height: usize,
+    /// The nodes with earlier bits
+    left: Option<Box<Node>>,
+    /// The nodes with later bits
+    right: Option<Box<Node>>,
+}
+
+/// Get a mask of the lowest `len` bits
+#[inline]
+fn mask(len: usize) -> u64 {
+    if len == WORD_BITS { u64::MAX } else { (1 << len) - 1 }
+}
+
+/// Get the position of the `index`-th one in `word`, counting from zero
+#[inline]
+fn select_in_word(word: u64, index: usize) -> usize {
+    let mut word = word;
+    for _ in 0..index {
+        // clear the lowest one
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+/// Concatenate the chunks `first` and `second` given as `(bits, len)`
+/// If the bits do not fit into one word, they are split into two chunks of at least half a word.
+#[inline]
+fn combine(first: (u64, usize), second: (u64, usize)) -> ((u64, usize), Option<(u64, usize)>) {
+    let len = first.1 + second.1;
+    let bits = first.0 as u128 | ((second.0 as u128) << first.1);
+    if len <= WORD_BITS {
+        return ((bits as u64, len), None);
+    }
+    let half = len / 2;
+    (((bits & ((1 << half) - 1)) as u64, half), Some(((bits >> half) as u64, len - half)))
+}
+
+/// Get the number of bits in `node`
+#[inline]
+fn size(node: &Option<Box<Node>>) -> usize {
+    node.as_ref().map_or(0, |node| node.size)
+}
+
+/// Get the number of ones in `node`
+#[inline]
+fn ones(node: &Option<Box<Node>>) -> usize {
+    node.as_ref().map_or(0, |node| node.ones)
+}
+
+/// Get the height of `node`
+#[inline]
+fn height(node: &Option<Box<Node>>) -> usize {
+    node.as_ref().map_or(0, |node| node.height)
+}
+
+impl Node {
+    /// Creates a node without children
+    fn new(bits: u64, len: usize) -> Box<Self> {
+        let mut node = Box::new(Node { bits, len, size: 0, ones: 0, height: 0, left: None, right: None });
+        node.update();
+        node
+    }
+
+    /// Recompute the subtree fields from the children
+    #[inline]
+    fn update(&mut self) {
+        self.size = size(&self.left) + self.len + size(&self.right);
+        self.ones = ones(&self.left) + self.bits.count_ones() as usize + ones(&self.right);
+        self.height = 1 + max(height(&self.left), height(&self.right));
+    }
+
+    /// Rotate the left child up
+    fn rotate_right(mut self: Box<Self>) -> Box<Self> {
+        let mut left = self.left.take().unwrap();
+        self.left = left.right.take();
+        self.update();
+        left.right = Some(self);
+        left.update();
+        left
+    }
+
+    /// Rotate the right child up
+    fn rotate_left(mut self: Box<Self>) -> Box<Self> {
+        let mut right = self.right.take().unwrap();
+        self.right = right.left.take();
+        self.update();
+        right.left = Some(self);
+        right.update();
+        right
+    }
+
+    /// Update the node and restore the AVL property, if the heights of the children differ by at most two
+    fn rebalance(mut self: Box<Self>) -> Box<Self> {
+        self.update();
+        let (left, right) = (height(&self.left), height(&self.right));
+        if left > right + 1 {
+            let child = self.left.as_ref().unwrap();
+            if height(&child.left) < height(&child.right) {
+                self.left = Some(self.left.take().unwrap().rotate_left());
+            }
+            return self.rotate_right();
+        }
+        if right > left + 1 {
+            let child = self.right.as_ref().unwrap();
+            if height(&child.right) < height(&child.left) {
+                self.right = Some(self.right.take().unwrap().rotate_right());
+            }
+            return self.rotate_left();
+        }
+        self
+    }
+
+    /// Build a balanced tree of `chunks` given as `(bits, len)`
+    fn build(chunks: &[(u64, usize)]) -> Option<Box<Self>> {
+        if chunks.is_empty() {
+            return None;
+        }
+        let middle = chunks.len() / 2;
+        let mut node = Node::new(chunks[middle].0, chunks[middle].1);
+        node.left = Self::build(&chunks[..middle]);
+        node.right = Self::build(&chunks[middle + 1..]);
+        node.update();
+        Some(node)
+    }
+
+    /// Insert a node with `bits` before all other bits of `node`
+    fn insert_first(node: Option<Box<Self>>, bits: u64, len: usize) -> Box<Self> {
+        match node {
+            None => Node::new(bits, len),
+            Some(mut node) => {
+                node.left = Some(Self::insert_first(node.left.take(), bits, len));
+                node.rebalance()
+            }
+        }
+    }
+
+    /// Insert a node with `bits` after all other bits of `node`
+    fn insert_last(node: Option<Box<Self>>, bits: u64, len: usize) -> Box<Self> {
+        match node {
+            None => Node::new(bits, len),
+            Some(mut node) => {
+                node.right = Some(Self::insert_last(node.right.take(), bits, len));
+                node.rebalance()
+            }
+        }
+    }
+
+    /// Whether the node has no children and less than `MIN_BITS` bits
+    #[inline]
+    fn is_underfull_leaf(&self) -> bool {
+        self.left.is_none() && self.right.is_none() && self.len < MIN_BITS
+    }
+
+    /// Insert `bit` at `index` of the subtree of `node`
+    fn insert(node: Option<Box<Self>>, index: usize, bit: bool) -> Box<Self> {
+        let Some(mut node) = node else { return Node::new(bit as u64, 1) };
+        let left_size = size(&node.left);
+        if index < left_size {
+            node.left = Some(Self::insert(node.left.take(), index, bit));
+        } else if index <= left_size + node.len {
+            if node.len == WORD_BITS {
+                // split a full node and move the upper half to a new node right after it
+                let half = WORD_BITS / 2;
+                node.right = Some(Self::insert_first(node.right.take(), node.bits >> half, WORD_BITS - half));
+                node.bits &= mask(half);
+                node.len = half;
+                return Self::insert(Some(node.rebalance()), index, bit);
+            }
+            let offset = index - left_size;
+            let high = node.bits >> offset;
+            node.bits = (node.bits & mask(offset)) | ((bit as u64) << offset) | high.checked_shl(offset as u32 + 1).unwrap_or(0);
+            node.len += 1;
+        } else {
+            node.right = Some(Self::insert(node.right.take(), index - left_size - node.len, bit));
+        }
+        node.rebalance()
+    }
+
+    /// Remove the first node of the subtree and return it together with the remaining subtree
+    fn remove_first(mut node: Box<Self>) -> (Box<Self>, Option<Box<Self>>) {
+        match node.left.take() {
+            None => {
+                let right = node.right.take();
+                (node, right)
+            }
+            Some(left) => {
+                let (first, left) = Self::remove_first(left);
+                node.left = left;
+                (first, Some(node.rebalance()))
+            }
+        }
+    }
+
+    /// Remove the last node of the subtree and return it together with the remaining subtree
+    fn remove_last(mut node: Box<Self>) -> (Box<Self>, Option<Box<Self>>) {
+        match node.right.take() {
+            None => {
+                let left = node.left.take();
+                (node, left)
+            }
+            Some(right) => {
+                let (last, right) = Self::remove_last(right);
+                node.right = right;
+                (last, Some(node.rebalance()))
+            }
+        }
+    }
+
+    /// Remove the bit at `index` of the subtree of `node` and return it together with the remaining subtree
+    /// A node with less than `MIN_BITS` bits is merged with the next or previous node inside its subtree.
+    /// If it has no children, its parent merges it when the recursion returns.
+    /// So every node except a single root keeps at least `MIN_BITS` bits.
+    fn remove(mut node: Box<Self>, index: usize) -> (bool, Option<Box<Self>>) {
+        let left_size = size(&node.left);
+        let bit;
+        if index < left_size {
+            let (removed, left) = Self::remove(node.left.take().unwrap(), index);
+            (bit, node.left) = (removed, left);
+            if node.left.as_ref().is_some_and(|left| left.is_underfull_leaf()) {
+                let mut left = node.left.take().unwrap();
+                let (chunk, rest) = combine((left.bits, left.len), (node.bits, node.len));
+                if let Some(rest) = rest {
+                    (left.bits, left.len) = chunk;
+                    left.update();
+                    node.left = Some(left);
+                    (node.bits, node.len) = rest;
+                } else {
+                    (node.bits, node.len) = chunk;
+                }
+            }
+        } else if index < left_size + node.len {
+            let offset = index - left_size;
+            bit = (node.bits >> offset) & 1 == 1;
+            node.bits = (node.bits & mask(offset)) | (node.bits.checked_shr(offset as u32 + 1).unwrap_or(0) << offset);
+            node.len -= 1;
+            if node.len < MIN_BITS {
+                if let Some(right) = node.right.take() {
+                    let (next, right) = Self::remove_first(right);
+                    let (chunk, rest) = combine((node.bits, node.len), (next.bits, next.len));
+                    (node.bits, node.len) = chunk;
+                    node.right = match rest {
+                        Some((bits, len)) => Some(Self::insert_first(right, bits, len)),
+                        None => right,
+                    };
+                } else if let Some(left) = node.left.take() {
+                    let (previous, left) = Self::remove_last(left);
+                    let (chunk, rest) = combine((previous.bits, previous.len), (node.bits, node.len));
+                    if let Some(rest) = rest {
+                        node.left = Some(Self::insert_last(left, chunk.0, chunk.1));
+                        (node.bits, node.len) = rest;
+                    } else {
+                        node.left = left;
+                        (node.bits, node.len) = chunk;
+                    }
+                } else if node.len == 0 {
+                    return (bit, None);
+                }
+            }
+        } else {
+            let (removed, right) = Self::remove(node.right.take().unwrap(), index - left_size - node.len);
+            (bit, node.right) = (removed, right);
+            if node.right.as_ref().is_some_and(|right| right.is_underfull_leaf()) {
+                let mut right = node.right.take().unwrap();
+                let (chunk, rest) = combine((node.bits, node.len), (right.bits, right.len));
+                (node.bits, node.len) = chunk;
+                if let Some(rest) = rest {
+                    (right.bits, right.len) = rest;
+                    right.update();
+                    node.right = Some(right);
+                }
+            }
+        }
+        (bit, Some(node.rebalance()))
+    }
+
+    /// Set the bit at `index` of the subtree to `bit`, which differs from the current bit
+    fn flip(&mut self, index: usize, bit: bool) {
+        if bit { self.ones += 1 } else { self.ones -= 1 }
+        let left_size = size(&self.left);
+        if index < left_size {
+            self.left.as_mut().unwrap().flip(index, bit);
+        } else if index < left_size + self.len {
+            self.bits ^= 1 << (index - left_size);
+        } else {
+            self.right.as_mut().unwrap().flip(index - left_size - self.len, bit);
+        }
+    }
+
+    /// Get the number of nodes in the subtree
+    fn count_nodes(node: &Option<Box<Self>>) -> usize {
+        node.as_ref().map_or(0, |node| 1 + Self::count_nodes(&node.left) + Self::count_nodes(&node.right))
+    }
+
+    /// Append the chunks of the subtree in order as `(bits, len)`
+    fn collect_chunks(node: &Option<Box<Self>>, chunks: &mut Vec<(u64, usize)>) {
+        if let Some(node) = node {
+            Self::collect_chunks(&node.left, chunks);
+            chunks.push((node.bits, node.len));
+            Self::collect_chunks(&node.right, chunks);
+        }
+    }
+}
+
+/// A bit vector supporting insertions and deletions at arbitrary positions
+/// The bits are stored in chunks of at most one word in the nodes of an AVL tree.
+/// Every node also stores the number of bits and ones in its subtree, so all operations take `O(log n)` time.
+/// A full node is split in half when a bit is inserted, and a node with less than a quarter word is merged with a neighbour,
+/// so the space stays proportional to the current length after many removals.
+/// For fast static queries, `to_bit_vector` converts it into a `BitVector`.
+#[derive(Default)]
+pub struct DynamicBitVector {
+    /// The root of the tree
+    root: Option<Box<Node>>,
+}
+
+impl From<&BitVector> for DynamicBitVector {
+    /// Creates a dynamic bit vector containing the bits of `bit_vector`
+    fn from(bit_vector: &BitVector) -> Self {
+        let mut chunks: Vec<(u64, usize)> = (0..bit_vector.len()).step_by(WORD_BITS).map(|start| {
+            let len = WORD_BITS.min(bit_vector.len() - start);
+            (bit_vector.get_bits(start, len), len)
+        }).collect();
+        // a short last chunk shares the bits with the previous one
+        if chunks.len() > 1 && chunks[chunks.len() - 1].1 < MIN_BITS {
+            let last = chunks.pop().unwrap();
+            let (chunk, rest) = combine(chunks.pop().unwrap(), last);
+            chunks.extend([chunk].into_iter().chain(rest));
+        }
+        DynamicBitVector { root: Node::build(&chunks) }
+    }
+}
+
+impl From<&DynamicBitVector> for BitVector {
+    /// Creates a static bit vector containing the bits of `bit_vector`
+    fn from(bit_vector: &DynamicBitVector) -> Self {
+        bit_vector.to_bit_vector()
+    }
+}
+
+impl FromIterator<bool> for DynamicBitVector {
+    /// Creates a dynamic bit vector from the bits of `iter`
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        Self::from(&iter.into_iter().collect::<BitVector>())
+    }
+}
+
+impl DynamicBitVector {
+    /// Creates an empty dynamic bit vector
+    pub fn new() -> Self {
+        DynamicBitVector { root: None }
+    }
+
+    /// Get the size of the bit vector including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<DynamicBitVector>() + Node::count_nodes(&self.root) * size_of::<Node>()
+    }
+
+    /// Get the length of the vector
+    #[inline]
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// Whether the vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the number of ones in the vector
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        ones(&self.root)
+    }
+
+    /// Get the bit at `index`
+    pub fn access(&self, index: usize) -> usize {
+        assert!(index < self.len(), "index out of bounds: the len is {} but the index is {index}", self.len());
+        let mut node = self.root.as_deref();
+        let mut index = index;
+        while let Some(current) = node {
+            let left_size = size(&current.left);
+            if index < left_size {
+                node = current.left.as_deref();
+            } else if index < left_size + current.len {
+                return ((current.bits >> (index - left_size)) & 1) as usize;
+            } else {
+                index -= left_size + current.len;
+                node = current.right.as_deref();
+            }
+        }
+        unreachable!("the index is inside the tree")
+    }
+
+    /// Insert `bit` at `index`, moving all later bits one position back
+    /// `index` may be equal to the length of the vector.
+    pub fn insert(&mut self, index: usize, bit: bool) {
+        assert!(index <= self.len(), "insertion index (is {index}) should be <= len (is {})", self.len());
+        self.root = Some(Node::insert(self.root.take(), index, bit));
+    }
+
+    /// Append `bit` at the end
+    pub fn push(&mut self, bit: bool) {
+        self.insert(self.len(), bit);
+    }
+
+    /// Remove the bit at `index` and return it, moving all later bits one position forward
+    pub fn remove(&mut self, index: usize) -> bool {
+        assert!(index < self.len(), "removal index (is {index}) should be < len (is {})", self.len());
+        let (bit, root) = Node::remove(self.root.take().unwrap(), index);
+        self.root = root;
+        bit
+    }
+
+    /// Set the bit at `index`
+    pub fn set(&mut self, index: usize, bit: bool) {
+        if (self.access(index) == 1) != bit {
+            self.root.as_mut().unwrap().flip(index, bit);
+        }
+    }
+
+    /// Get the number of ones before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    pub fn rank1(&self, index: usize) -> usize {
+        let mut node = self.root.as_deref();
+        let mut index = index;
+        let mut rank = 0;
+        while let Some(current) = node {
+            let left_size = size(&current.left);
+            if index < left_size {
+                node = current.left.as_deref();
+                continue;
+            }
+            rank += ones(&current.left);
+            index -= left_size;
+            if index <= current.len {
+                return rank + (current.bits & mask(index)).count_ones() as usize;
+            }
+            rank += current.bits.count_ones() as usize;
+            index -= current.len;
+            node = current.right.as_deref();
+        }
+        rank
+    }
+
+    /// Get the number of zeros before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    pub fn rank0(&self, index: usize) -> usize {
+        index.min(self.len()) - self.rank1(index)
+    }
+
+    /// Get the position of the `index`-th one, counting from zero
+    /// Returns `None` if the vector contains at most `index` ones.
+    pub fn select1(&self, index: usize) -> Option<usize> {
+        self.select::<true>(index)
+    }
+
+    /// Get the position of the `index`-th zero, counting from zero
+    /// Returns `None` if the vector contains at most `index` zeros.
+    pub fn select0(&self, index: usize) -> Option<usize> {
+        self.select::<false>(index)
+    }
+
+    /// Get the position of the `index`-th zero/one, counting from zero
+    fn select<const BIT: bool>(&self, index: usize) -> Option<usize> {
+        // the number of zeros/ones in a subtree or a node
+        let count = |ones: usize, size: usize| if BIT { ones } else { size - ones };
+        let mut node = self.root.as_deref();
+        let mut index = index;
+        let mut position = 0;
+        while let Some(current) = node {
+            let left = count(ones(&current.left), size(&current.left));
+            if index < left {
+                node = current.left.as_deref();
+                continue;
+            }
+            index -= left;
+            position += size(&current.left);
+            let word = if BIT { current.bits } else { !current.bits & mask(current.len) };
+            let in_node = word.count_ones() as usize;
+            if index < in_node {
+                return Some(position + select_in_word(word, index));
+            }
+            index -= in_node;
+            position += current.len;
+            node = current.right.as_deref();
+        }
+        None
+    }
+
+    /// Converts the dynamic bit vector into a static `BitVector`
+    pub fn to_bit_vector(&self) -> BitVector {
+        let mut chunks = Vec::new();
+        Node::collect_chunks(&self.root, &mut chunks);
+        chunks.into_iter().flat_map(|(bits, len)| (0..len).map(move |i| (bits >> i) & 1 == 1)).collect()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::mem::size_of;
+    use crate::{BitVector, DynamicBitVector};
+    use crate::dynamic::{Node, MIN_BITS};
+
+    fn test_against_reference(bit_vector: &DynamicBitVector, reference: &[bool]) {
+        assert_eq!(bit_vector.len(), reference.len());
+        assert_eq!(bit_vector.to_bit_vector(), reference.iter().copied().collect::<BitVector>());
+        let mut ones = 0;
+        for (i, &bit) in reference.iter().enumerate() {
+            assert_eq!(bit_vector.access(i), bit as usize);
+            assert_eq!(bit_vector.rank1(i), ones);
+            if bit {
+                assert_eq!(bit_vector.select1(ones), Some(i));
+                ones += 1;
+            } else {
+                assert_eq!(bit_vector.select0(i - ones), Some(i));
+            }
+        }
+        assert_eq!(bit_vector.rank1(reference.len()), ones);
+        assert_eq!(bit_vector.count_ones(), ones);
+        assert_eq!(bit_vector.select1(ones), None);
+        assert_eq!(bit_vector.select0(reference.len() - ones), None);
+    }
+
+    #[test]
+    fn test_random_operations() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let mut reference = Vec::new();
+        let mut bit_vector = DynamicBitVector::new();
+        for step in 0..20000 {
+            // grow at first, then shrink again
+            let grow = if step < 15000 { 3 } else { 1 };
+            match rng.gen_range(0..5) {
+                operation if operation < grow || reference.is_empty() => {
+                    let index = rng.gen_range(0..=reference.len());
+                    let bit = rng.gen_range(0..2) == 1;
+                    reference.insert(index, bit);
+                    bit_vector.insert(index, bit);
+                }
+                3 => {
+                    let index = rng.gen_range(0..reference.len());
+                    let bit = rng.gen_range(0..2) == 1;
+                    reference[index] = bit;
+                    bit_vector.set(index, bit);
+                }
+                _ => {
+                    let index = rng.gen_range(0..reference.len());
+                    assert_eq!(bit_vector.remove(index), reference.remove(index));
+                }
+            }
+            if step % 2000 == 0 {
+                test_against_reference(&bit_vector, &reference);
+            }
+        }
+        test_against_reference(&bit_vector, &reference);
+    }
+
+    #[test]
+    fn test_conversion() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let reference: Vec<bool> = (0..5000).map(|_| rng.gen_range(0..3) == 0).collect();
+        let static_bits: BitVector = reference.iter().copied().collect();
+        let mut bit_vector = DynamicBitVector::from(&static_bits);
+        test_against_reference(&bit_vector, &reference);
+        assert_eq!(BitVector::from(&bit_vector), static_bits);
+        // inserting at the front moves every bit
+        bit_vector.insert(0, true);
+        assert_eq!(bit_vector.rank1(5001), static_bits.rank1(5000) + 1);
+        assert_eq!(bit_vector.select1(1), static_bits.select1(0).map(|position| position + 1));
+    }
+
+    #[test]
+    fn test_mass_delete() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let mut reference: Vec<bool> = (0..100000).map(|_| rng.gen_range(0..2) == 1).collect();
+        let mut bit_vector: DynamicBitVector = reference.iter().copied().collect();
+        for len in (5000..reference.len()).rev() {
+            let index = rng.gen_range(0..=len);
+            assert_eq!(bit_vector.remove(index), reference.remove(index));
+        }
+        test_against_reference(&bit_vector, &reference);
+        // every node except a single root holds at least `MIN_BITS` bits
+        let max_nodes = reference.len() / MIN_BITS + 1;
+        assert!(bit_vector.get_size() <= size_of::<DynamicBitVector>() + max_nodes * size_of::<Node>());
+    }
+
+    #[test]
+    fn test_sequential() {
+        let mut bit_vector = DynamicBitVector::new();
+        for i in 0..10000 {
+            bit_vector.push(i % 3 == 0);
+        }
+        let reference: Vec<bool> = (0..10000).map(|i| i % 3 == 0).collect();
+        test_against_reference(&bit_vector, &reference);
+        while !bit_vector.is_empty() {
+            bit_vector.remove(0);
+        }
+        assert_eq!(bit_vector.select0(0), None);
+        assert!(bit_vector.get_size() > 0);
+    }
+}
@@ -0,0 +1,243 @@
+use std::mem::size_of;
+use std::ops::Range;
+use crate::{BitVector, WaveletTree};
+
+/// Every `SAMPLE_RATE`-th text position is sampled in the suffix array and the inverse suffix array
+const SAMPLE_RATE: usize = 32;
+
+/// The number of different symbols including the sentinel
+const SIGMA: usize = 257;
+
+/// An FM-index of a byte string supporting substring search
+/// The text is terminated by a sentinel smaller than every byte, so byte `c` is stored as symbol `c + 1`.
+/// The Burrows-Wheeler transform (BWT) of the text is stored in a `WaveletTree`,
+/// which allows to count the occurrences of a pattern using backward search with rank queries.
+/// To locate occurrences, the suffix array is sampled at text positions divisible by `SAMPLE_RATE`.
+/// Rows whose position is sampled are marked in a `BitVector`, so the sample of a row is found with rank.
+/// The other positions are found by stepping backward through the text using the LF mapping until a sampled row is reached.
+pub struct FmIndex {
+    /// The BWT of the text including the sentinel
+    bwt: WaveletTree<u16>,
+    /// For every symbol the number of smaller symbols in the text including the sentinel
+    counts: Vec<usize>,
+    /// The rows of the suffix array whose text position is sampled
+    sampled: BitVector,
+    /// The sampled text positions ordered by row
+    samples: Vec<usize>,
+    /// The rows of the text positions divisible by `SAMPLE_RATE`
+    inverse_samples: Vec<usize>,
+}
+
+/// Build the suffix array of `text` terminated by a sentinel using prefix doubling
+fn suffix_array(text: &[u8]) -> Vec<usize> {
+    let len = text.len() + 1;
+    let mut ranks: Vec<usize> = text.iter().map(|&c| c as usize + 1).chain([0]).collect();
+    let mut suffixes: Vec<usize> = (0..len).collect();
+    let mut next_ranks = vec![0; len];
+    let mut k = 1;
+    loop {
+        // the rank of the first 2k symbols of every suffix
+        let key = |i: usize| (ranks[i], if i + k < len { ranks[i + k] + 1 } else { 0 });
+        suffixes.sort_unstable_by_key(|&i| key(i));
+        next_ranks[suffixes[0]] = 0;
+        for i in 1..len {
+            next_ranks[suffixes[i]] = next_ranks[suffixes[i - 1]] + (key(suffixes[i - 1]) != key(suffixes[i])) as usize;
+        }
+        std::mem::swap(&mut ranks, &mut next_ranks);
+        if ranks[suffixes[len - 1]] == len - 1 {
+            return suffixes;
+        }
+        k *= 2;
+    }
+}
+
+impl FmIndex {
+    /// Creates an FM-index of `text`
+    pub fn new(text: &[u8]) -> Self {
+        let suffixes = suffix_array(text);
+        let bwt: Vec<u16> = suffixes.iter().map(|&i| if i == 0 { 0 } else { text[i - 1] as u16 + 1 }).collect();
+        let mut counts = vec![0; SIGMA + 1];
+        for &symbol in &bwt {
+            counts[symbol as usize + 1] += 1;
+        }
+        for symbol in 1..counts.len() {
+            counts[symbol] += counts[symbol - 1];
+        }
+        let mut sampled: BitVector = suffixes.iter().map(|&i| i % SAMPLE_RATE == 0).collect();
+        sampled.init_rank_structures();
+        let samples = suffixes.iter().copied().filter(|&i| i % SAMPLE_RATE == 0).collect();
+        let mut inverse_samples = vec![0; text.len() / SAMPLE_RATE + 1];
+        for (row, &i) in suffixes.iter().enumerate() {
+            if i % SAMPLE_RATE == 0 {
+                inverse_samples[i / SAMPLE_RATE] = row;
+            }
+        }
+        FmIndex {
+            bwt: WaveletTree::new(&bwt),
+            counts,
+            sampled,
+            samples,
+            inverse_samples,
+        }
+    }
+
+    /// Get the size of the FM-index including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<FmIndex>() - size_of::<WaveletTree<u16>>()
+        + self.bwt.get_size() + self.sampled.get_size()
+        + (self.counts.capacity() + self.samples.capacity() + self.inverse_samples.capacity()) * size_of::<usize>()
+    }
+
+    /// Get the length of the text
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bwt.len() - 1
+    }
+
+    /// Whether the text is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Map `row` to the row of the suffix starting one position earlier in the text
+    #[inline]
+    fn lf(&self, row: usize) -> (u16, usize) {
+        let symbol = self.bwt.access(row);
+        (symbol, self.counts[symbol as usize] + self.bwt.rank(symbol, row))
+    }
+
+    /// Get the rows of the suffixes starting with `pattern`
+    fn backward_search(&self, pattern: &[u8]) -> Range<usize> {
+        let mut rows = 0..self.bwt.len();
+        for &c in pattern.iter().rev() {
+            let symbol = c as u16 + 1;
+            let count = self.counts[symbol as usize];
+            rows = count + self.bwt.rank(symbol, rows.start)..count + self.bwt.rank(symbol, rows.end);
+            if rows.is_empty() {
+                break;
+            }
+        }
+        rows
+    }
+
+    /// Get the number of occurrences of `pattern` in the text
+    /// The empty pattern occurs at every position including the end of the text.
+    pub fn count(&self, pattern: &[u8]) -> usize {
+        self.backward_search(pattern).len()
+    }
+
+    /// Get the text position of the suffix in `row`
+    fn position(&self, row: usize) -> usize {
+        let mut row = row;
+        let mut steps = 0;
+        while self.sampled.access(row) == 0 {
+            (_, row) = self.lf(row);
+            steps += 1;
+        }
+        self.samples[self.sampled.rank1(row)] + steps
+    }
+
+    /// Get the starting positions of all occurrences of `pattern` in the text in increasing order
+    pub fn locate(&self, pattern: &[u8]) -> Vec<usize> {
+        let mut positions: Vec<usize> = self.backward_search(pattern).map(|row| self.position(row)).collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    /// Get the bytes of the text in `range`
+    pub fn extract(&self, range: Range<usize>) -> Vec<u8> {
+        assert!(range.start <= range.end && range.end <= self.len(), "range {range:?} is out of bounds for length {}", self.len());
+        // start at the first sampled position at or after the end, or at the sentinel in row zero
+        let sample = range.end.div_ceil(SAMPLE_RATE);
+        let (mut position, mut row) = if sample * SAMPLE_RATE <= self.len() {
+            (sample * SAMPLE_RATE, self.inverse_samples[sample])
+        } else {
+            (self.len(), 0)
+        };
+        let mut text = Vec::with_capacity(range.len());
+        while position > range.start {
+            let (symbol, previous) = self.lf(row);
+            position -= 1;
+            row = previous;
+            if position < range.end {
+                text.push((symbol - 1) as u8);
+            }
+        }
+        text.reverse();
+        text
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::fm_index::suffix_array;
+    use crate::FmIndex;
+
+    fn naive_locate(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+        (0..=text.len()).filter(|&i| text[i..].starts_with(pattern)).collect()
+    }
+
+    fn test_against_naive(text: &[u8], rng: &mut ChaCha8Rng) {
+        let index = FmIndex::new(text);
+        assert_eq!(index.len(), text.len());
+        assert_eq!(index.extract(0..text.len()), text);
+        for _ in 0..200 {
+            let start = rng.gen_range(0..=text.len());
+            let end = rng.gen_range(start..=text.len().min(start + 20));
+            // patterns which occur in the text as well as random ones
+            let pattern: Vec<u8> = if rng.gen_range(0..2) == 0 {
+                text[start..end].to_vec()
+            } else {
+                (start..end).map(|_| rng.gen_range(b'a'..=b'd')).collect()
+            };
+            let positions = naive_locate(text, &pattern);
+            assert_eq!(index.count(&pattern), positions.len());
+            assert_eq!(index.locate(&pattern), positions);
+            assert_eq!(index.extract(start..end), &text[start..end]);
+        }
+    }
+
+    #[test]
+    fn test_suffix_array() {
+        let text = b"mississippi";
+        let mut expected: Vec<usize> = (0..=text.len()).collect();
+        expected.sort_by_key(|&i| &text[i..]);
+        assert_eq!(suffix_array(text), expected);
+    }
+
+    #[test]
+    fn test_random_texts() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let text: Vec<u8> = (0..5000).map(|_| rng.gen_range(b'a'..=b'd')).collect();
+        test_against_naive(&text, &mut rng);
+        // long repetitions need many doubling steps
+        let text: Vec<u8> = b"abracadabra".iter().cycle().take(3000).copied().collect();
+        test_against_naive(&text, &mut rng);
+    }
+
+    #[test]
+    fn test_all_bytes() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let text: Vec<u8> = (0..2000).map(|_| rng.gen_range(0..=255)).collect();
+        let index = FmIndex::new(&text);
+        assert_eq!(index.extract(0..text.len()), text);
+        assert_eq!(index.locate(&text[100..104]), naive_locate(&text, &text[100..104]));
+        assert_eq!(index.count(&[0]), text.iter().filter(|&&c| c == 0).count());
+        assert_eq!(index.count(&[255]), text.iter().filter(|&&c| c == 255).count());
+    }
+
+    #[test]
+    fn test_small() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        test_against_naive(b"", &mut rng);
+        test_against_naive(b"a", &mut rng);
+        test_against_naive(b"mississippi", &mut rng);
+        let index = FmIndex::new(b"mississippi");
+        assert_eq!(index.locate(b"ssi"), vec![2, 5]);
+        assert_eq!(index.count(b""), 12);
+    }
+}
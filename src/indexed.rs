@@ -0,0 +1,287 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, Range};
+use crate::{BitVector, Unit};
+use crate::rank::RankAccelerator;
+use crate::select::SelectAccelerator;
+
+/// An immutable bit vector whose rank and select accelerators are always built
+/// It is created using `BitVector::build` and can be turned back into a mutable `BitVector` using `into_inner`.
+/// Because the accelerators are not optional, queries never have to check whether they exist.
+/// Like for `BitVector`, equality and hashing only consider the bits.
+#[derive(Clone)]
+pub struct IndexedBitVector {
+    /// The raw bits
+    bit_vector: BitVector,
+    /// Used to accelerate rank operations
+    rank_accelerator: RankAccelerator,
+    /// Used to accelerate zero select operations
+    select_accelerator_0: SelectAccelerator<false>,
+    /// Used to accelerate one select operations
+    select_accelerator_1: SelectAccelerator<true>,
+}
+
+impl BitVector {
+    /// Builds all accelerators and turns the bit vector into an `IndexedBitVector`
+    /// Accelerators that were already created by previous queries are reused.
+    pub fn build(self) -> IndexedBitVector {
+        let mut bit_vector = self;
+        let rank_accelerator = bit_vector.rank_accelerator.take().unwrap_or_else(|| bit_vector.build_rank_accelerator());
+        let select_accelerator_0 = bit_vector.select_accelerator_0.take().unwrap_or_else(|| bit_vector.build_select_accelerator());
+        let select_accelerator_1 = bit_vector.select_accelerator_1.take().unwrap_or_else(|| bit_vector.build_select_accelerator());
+        IndexedBitVector {
+            bit_vector,
+            rank_accelerator,
+            select_accelerator_0,
+            select_accelerator_1,
+        }
+    }
+}
+
+impl From<BitVector> for IndexedBitVector {
+    /// Builds all accelerators of `bit_vector`
+    fn from(bit_vector: BitVector) -> Self {
+        bit_vector.build()
+    }
+}
+
+impl PartialEq for IndexedBitVector {
+    /// Two bit vectors are equal if they contain the same bits
+    fn eq(&self, other: &Self) -> bool {
+        self.bit_vector == other.bit_vector
+    }
+}
+
+impl Eq for IndexedBitVector {}
+
+impl Hash for IndexedBitVector {
+    /// Hashes the bits of the bit vector
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bit_vector.hash(state);
+    }
+}
+
+impl Index<usize> for IndexedBitVector {
+    type Output = bool;
+
+    /// Get the bit at `index`
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.bit_vector[index]
+    }
+}
+
+impl fmt::Debug for IndexedBitVector {
+    /// Shows the length, the density of ones and the first bits
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.bit_vector, f)
+    }
+}
+
+impl fmt::Display for IndexedBitVector {
+    /// Writes one '0' or '1' per bit, so the output can be loaded using `BitVector::load_from_string`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.bit_vector, f)
+    }
+}
+
+impl IndexedBitVector {
+    /// Drops the accelerators and returns the raw bit vector, e.g. to modify it
+    pub fn into_inner(self) -> BitVector {
+        self.bit_vector
+    }
+
+    /// Get the raw bit vector
+    #[inline]
+    pub fn as_bit_vector(&self) -> &BitVector {
+        &self.bit_vector
+    }
+
+    /// Get the size of the bit vector including space on the heap
+    pub fn get_size(&self) -> usize {
+        self.bit_vector.get_size() + self.get_size_rank() + self.get_size_select_0() + self.get_size_select_1()
+    }
+
+    /// Estimate the size of an indexed bit vector with `len` bits of which `ones` are ones without building it
+    pub(crate) fn estimate_size(len: usize, ones: usize) -> usize {
+        BitVector::estimate_size(len)
+        + RankAccelerator::estimate_size(len)
+        + SelectAccelerator::<false>::estimate_size(len, len - ones)
+        + SelectAccelerator::<true>::estimate_size(len, ones)
+    }
+
+    /// Get the size of the rank accelerator including space on the heap
+    pub fn get_size_rank(&self) -> usize {
+        self.rank_accelerator.get_size()
+    }
+
+    /// Get the size of the select zero accelerator including space on the heap
+    pub fn get_size_select_0(&self) -> usize {
+        self.select_accelerator_0.get_size()
+    }
+
+    /// Get the size of the select one accelerator including space on the heap
+    pub fn get_size_select_1(&self) -> usize {
+        self.select_accelerator_1.get_size()
+    }
+
+    /// Get the length of the vector
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bit_vector.len()
+    }
+
+    /// Whether the vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bit_vector.is_empty()
+    }
+
+    /// Get the bit at `index`
+    #[inline]
+    pub fn access(&self, index: usize) -> usize {
+        self.bit_vector.access(index)
+    }
+
+    /// Get the word starting at `index`
+    #[inline]
+    pub fn access_block(&self, index: usize) -> Unit {
+        self.bit_vector.access_block(index)
+    }
+
+    /// Get the number of one bits in the `range`
+    #[inline]
+    pub fn count_ones(&self, range: Range<usize>) -> usize {
+        self.bit_vector.count_ones(range)
+    }
+
+    /// Get the number of zeros before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    #[inline]
+    pub fn rank0(&self, index: usize) -> usize {
+        self.rank_accelerator.rank0(index, &self.bit_vector)
+    }
+
+    /// Get the number of ones before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    /// For every `k` less than the number of ones `rank1(select1(k)) == k` holds.
+    #[inline]
+    pub fn rank1(&self, index: usize) -> usize {
+        self.rank_accelerator.rank1(index, &self.bit_vector)
+    }
+
+    /// Get the number of zero/one's up to and including `index`, i.e. in `0..=index`
+    #[inline]
+    pub fn rank_inclusive(&self, bit: bool, index: usize) -> usize {
+        if bit { self.rank1(index + 1) } else { self.rank0(index + 1) }
+    }
+
+    /// Get the position of the `index`-th zero, counting from zero
+    /// Returns `None` if the vector contains at most `index` zeros.
+    /// For every `k` less than the number of zeros `rank0(select0(k)) == k` holds.
+    #[inline]
+    pub fn select0(&self, index: usize) -> Option<usize> {
+        self.select_accelerator_0.select(index, &self.bit_vector)
+    }
+
+    /// Get the position of the `index`-th one, counting from zero
+    /// Returns `None` if the vector contains at most `index` ones.
+    /// For every `k` less than the number of ones `rank1(select1(k)) == k` holds.
+    #[inline]
+    pub fn select1(&self, index: usize) -> Option<usize> {
+        self.select_accelerator_1.select(index, &self.bit_vector)
+    }
+
+    /// Get the number of zero/one's before `index`
+    #[inline]
+    #[deprecated(note = "use `rank0` or `rank1` instead")]
+    pub fn rank(&self, bit: bool, index: usize) -> usize {
+        if bit { self.rank1(index) } else { self.rank0(index) }
+    }
+
+    /// Get the position of the `index`-th zero/one, counting from one
+    #[inline]
+    #[deprecated(note = "use the zero based `select0` or `select1` instead")]
+    pub fn select(&self, bit: bool, index: usize) -> usize {
+        // index -1 because select0 and select1 are zero based
+        if bit { self.select1(index-1) } else { self.select0(index-1) }.expect("No ith zero/one found")
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::BitVector;
+
+    fn random_data(len: usize) -> String {
+        let mut data = String::new();
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for _ in 0..len {
+            if rng.gen_range(0..=1) == 0 {
+                data += "0";
+            } else {
+                data += "1";
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_rank_select() {
+        let data = random_data(65536);
+        let indexed = BitVector::load_from_string(&data).build();
+        assert_eq!(indexed.len(), data.len());
+        let mut ones = 0;
+        for (i, c) in data.chars().enumerate() {
+            assert_eq!(indexed.access(i), if c == '1' { 1 } else { 0 });
+            assert_eq!(indexed.rank1(i), ones);
+            assert_eq!(indexed.rank0(i), i - ones);
+            if c == '1' {
+                assert_eq!(indexed.select1(ones), Some(i));
+                ones += 1;
+            } else {
+                assert_eq!(indexed.select0(i - ones), Some(i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_reuses_accelerators() {
+        let data = random_data(4096);
+        let bit_vector = BitVector::load_from_string(&data);
+        // create the rank accelerator lazily before building
+        bit_vector.rank1(100);
+        assert!(bit_vector.rank_accelerator.get().is_some());
+        let indexed = bit_vector.build();
+        assert_eq!(indexed.get_size_rank(), BitVector::load_from_string(&data).build().get_size_rank());
+        assert_eq!(indexed.rank1(100), data[..100].chars().filter(|&c| c == '1').count());
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let data = random_data(1000);
+        let indexed = BitVector::load_from_string(&data).build();
+        let bit_vector = indexed.into_inner();
+        // the raw vector has no accelerators anymore
+        assert_eq!(bit_vector.get_size_rank(), 0);
+        assert_eq!(bit_vector.get_size_select_0(), 0);
+        assert_eq!(bit_vector.get_size_select_1(), 0);
+        for (i, c) in data.chars().enumerate() {
+            assert_eq!(bit_vector.access(i), if c == '1' { 1 } else { 0 });
+        }
+    }
+
+    #[test]
+    fn test_std_traits() {
+        let data = random_data(1000);
+        let indexed = BitVector::load_from_string(&data).build();
+        let clone = indexed.clone();
+        assert_eq!(clone, indexed);
+        assert_eq!(clone.to_string(), data);
+        for i in (0..data.len()).step_by(7) {
+            assert_eq!(clone[i], indexed.access(i) == 1);
+            assert_eq!(clone.rank1(i), indexed.rank1(i));
+        }
+    }
+}
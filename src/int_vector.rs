@@ -0,0 +1,217 @@
+use std::fmt;
+use std::mem::size_of;
+use crate::BitVector;
+use crate::wavelet_tree::bits_for;
+
+/// A vector of integers with a fixed number of bits each
+/// The `i`-th integer is stored in the bits `i * width..(i + 1) * width` of a `BitVector`.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct IntVector {
+    /// The packed integers
+    bits: BitVector,
+    /// The number of bits of every integer
+    width: usize,
+    /// The number of integers
+    len: usize,
+}
+
+impl FromIterator<u64> for IntVector {
+    /// Creates an integer vector of the values of `iter` using the smallest width which fits the maximum value
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        Self::from_slice(&iter.into_iter().collect::<Vec<u64>>())
+    }
+}
+
+impl From<&[u64]> for IntVector {
+    /// Creates an integer vector of `values` using the smallest width which fits the maximum value
+    fn from(values: &[u64]) -> Self {
+        Self::from_slice(values)
+    }
+}
+
+impl fmt::Debug for IntVector {
+    /// Prints the width and the integers
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IntVector")
+            .field("width", &self.width)
+            .field("values", &self.iter().collect::<Vec<u64>>())
+            .finish()
+    }
+}
+
+impl IntVector {
+    /// Creates an empty integer vector storing integers of `width` bits
+    pub fn new(width: usize) -> Self {
+        Self::with_len(width, 0)
+    }
+
+    /// Creates an integer vector of `len` zeros with `width` bits each
+    pub fn with_len(width: usize, len: usize) -> Self {
+        assert!((1..=u64::BITS as usize).contains(&width), "width {width} is not in 1..=64");
+        let mut bits = BitVector::new();
+        bits.resize(len * width);
+        IntVector {
+            bits,
+            width,
+            len,
+        }
+    }
+
+    /// Creates an integer vector of `values` using the smallest width which fits the maximum value
+    pub fn from_slice(values: &[u64]) -> Self {
+        Self::from_slice_with_width(values, bits_for(values.iter().copied().max().unwrap_or(0)))
+    }
+
+    /// Creates an integer vector of `values` with `width` bits each
+    /// Panics if a value does not fit into `width` bits.
+    pub fn from_slice_with_width(values: &[u64], width: usize) -> Self {
+        let mut int_vector = Self::with_len(width, values.len());
+        for (i, &value) in values.iter().enumerate() {
+            int_vector.set(i, value);
+        }
+        int_vector
+    }
+
+    /// Get the size of the integer vector including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<IntVector>() + self.bits.get_size()
+    }
+
+    /// Get the packed integers as bit vector
+    #[inline]
+    pub fn as_bit_vector(&self) -> &BitVector {
+        &self.bits
+    }
+
+    /// Get the number of bits of every integer
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the number of integers
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no integers
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the integer at `index`
+    #[inline]
+    pub fn get(&self, index: usize) -> u64 {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        self.bits.get_bits(index * self.width, self.width)
+    }
+
+    /// Set the integer at `index` to `value`
+    /// Panics if `value` does not fit into `width` bits.
+    #[inline]
+    pub fn set(&mut self, index: usize, value: u64) {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        self.bits.set_bits(index * self.width, self.width, value);
+    }
+
+    /// Append `value` to the end
+    /// Panics if `value` does not fit into `width` bits.
+    pub fn push(&mut self, value: u64) {
+        // check before growing, so a panic does not leave a zero behind
+        assert!(self.width == u64::BITS as usize || value >> self.width == 0, "value {value} does not fit into {} bits", self.width);
+        self.len += 1;
+        self.bits.resize(self.len * self.width);
+        self.set(self.len - 1, value);
+    }
+
+    /// Remove the last integer and return it
+    /// Returns `None` if the vector is empty.
+    pub fn pop(&mut self) -> Option<u64> {
+        if self.is_empty() {
+            return None;
+        }
+        let value = self.get(self.len - 1);
+        self.len -= 1;
+        self.bits.resize(self.len * self.width);
+        Some(value)
+    }
+
+    /// Iterate over the integers
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).map(|i| self.get(i))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::IntVector;
+
+    #[test]
+    fn test_widths() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for width in [1, 3, 7, 8, 13, 31, 32, 33, 63, 64] {
+            let max = if width == 64 { u64::MAX } else { (1 << width) - 1 };
+            let values: Vec<u64> = (0..1000).map(|_| rng.gen_range(0..=max)).collect();
+            let mut int_vector = IntVector::from_slice_with_width(&values, width);
+            assert_eq!(int_vector.width(), width);
+            assert_eq!(int_vector.iter().collect::<Vec<u64>>(), values);
+            // overwriting a value must not change its neighbours
+            int_vector.set(500, max);
+            int_vector.set(501, 0);
+            for (i, &value) in values.iter().enumerate() {
+                let expected = match i { 500 => max, 501 => 0, _ => value };
+                assert_eq!(int_vector.get(i), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_auto_width() {
+        assert_eq!(IntVector::from_slice(&[0, 1, 2, 3]).width(), 2);
+        assert_eq!(IntVector::from_slice(&[4]).width(), 3);
+        assert_eq!(IntVector::from_slice(&[]).width(), 1);
+        assert_eq!(IntVector::from_slice(&[u64::MAX]).width(), 64);
+        let int_vector: IntVector = (0..100u64).map(|i| i * 10).collect();
+        assert_eq!(int_vector.width(), 10);
+        assert_eq!(int_vector.get(99), 990);
+        assert_eq!(IntVector::from(&[5u64, 6][..]), IntVector::from_slice_with_width(&[5, 6], 3));
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut int_vector = IntVector::new(5);
+        for i in 0..300 {
+            int_vector.push(i % 32);
+        }
+        assert_eq!(int_vector.len(), 300);
+        assert_eq!(int_vector.iter().collect::<Vec<u64>>(), (0..300).map(|i| i % 32).collect::<Vec<u64>>());
+        for i in (0..300).rev() {
+            assert_eq!(int_vector.pop(), Some(i % 32));
+        }
+        assert_eq!(int_vector.pop(), None);
+        assert!(int_vector.is_empty());
+        // popped values must not reappear after pushing again
+        assert_eq!(int_vector, IntVector::new(5));
+        int_vector.push(0);
+        assert_eq!(int_vector.get(0), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_large() {
+        IntVector::new(3).push(8);
+    }
+
+    #[test]
+    fn test_too_large_keeps_len() {
+        let mut int_vector = IntVector::from_slice_with_width(&[1, 2], 3);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| int_vector.push(8)));
+        assert!(result.is_err());
+        assert_eq!(int_vector, IntVector::from_slice_with_width(&[1, 2], 3));
+    }
+}
@@ -0,0 +1,270 @@
+use std::mem::size_of;
+use std::ops::Range;
+use crate::BitVector;
+
+/// The number of rows and columns every node of a `K2Tree` is divided into
+const K: usize = 2;
+
+/// A k²-tree storing the adjacency matrix of a sparse directed graph
+/// The matrix is padded to a power of `K` and recursively divided into `K * K` submatrices.
+/// Every submatrix is represented by a bit telling whether it contains any edge,
+/// and only non-empty submatrices are divided further.
+/// The bits are stored level by level: all levels except the last in `tree`, the single cells in `leaves`.
+/// The children of the one at position `p` start at `rank1(p + 1) * K * K`, so navigation only needs rank.
+pub struct K2Tree {
+    /// The number of nodes of the graph
+    num_nodes: usize,
+    /// The number of edges of the graph
+    num_edges: usize,
+    /// The number of rows and columns of the padded matrix
+    size: usize,
+    /// The bits of all internal levels with rank structures
+    tree: BitVector,
+    /// The bits of the last level
+    leaves: BitVector,
+}
+
+impl K2Tree {
+    /// Creates a k²-tree of the graph with `num_nodes` nodes and `edges` given as `(source, target)`
+    /// Duplicate edges are stored once.
+    pub fn from_edges(num_nodes: usize, edges: &[(usize, usize)]) -> Self {
+        for &(source, target) in edges {
+            assert!(source < num_nodes && target < num_nodes, "Edge ({source}, {target}) is out of bounds for {num_nodes} nodes");
+        }
+        let mut edges = edges.to_vec();
+        edges.sort_unstable();
+        edges.dedup();
+        let num_edges = edges.len();
+        let mut size = K;
+        while size < num_nodes {
+            size *= K;
+        }
+        let mut tree = Vec::new();
+        let mut leaves = Vec::new();
+        // the non-empty submatrices of the current level in level order as (row, column, edges)
+        let mut level = if edges.is_empty() { Vec::new() } else { vec![(0, 0, edges)] };
+        let mut child_size = size / K;
+        while !level.is_empty() {
+            let bits = if child_size == 1 { &mut leaves } else { &mut tree };
+            let mut next_level = Vec::new();
+            for (row, column, edges) in level {
+                let mut children = vec![Vec::new(); K * K];
+                for (source, target) in edges {
+                    children[(source - row) / child_size * K + (target - column) / child_size].push((source, target));
+                }
+                for (i, child) in children.into_iter().enumerate() {
+                    bits.push(!child.is_empty());
+                    if !child.is_empty() && child_size > 1 {
+                        next_level.push((row + i / K * child_size, column + i % K * child_size, child));
+                    }
+                }
+            }
+            level = next_level;
+            child_size /= K;
+        }
+        let mut tree: BitVector = tree.into_iter().collect();
+        tree.init_rank_structures();
+        K2Tree {
+            num_nodes,
+            num_edges,
+            size,
+            tree,
+            leaves: leaves.into_iter().collect(),
+        }
+    }
+
+    /// Get the size of the k²-tree including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<K2Tree>() + self.tree.get_size() + self.leaves.get_size()
+    }
+
+    /// Get the number of nodes of the graph
+    #[inline]
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    /// Get the number of edges of the graph
+    #[inline]
+    pub fn num_edges(&self) -> usize {
+        self.num_edges
+    }
+
+    /// Get the bit at `position` of the concatenation of `tree` and `leaves`
+    #[inline]
+    fn bit(&self, position: usize) -> bool {
+        if position < self.tree.len() {
+            self.tree.access(position) == 1
+        } else {
+            self.leaves.access(position - self.tree.len()) == 1
+        }
+    }
+
+    /// Get the position of the first child of the one at `position`
+    #[inline]
+    fn children(&self, position: usize) -> usize {
+        self.tree.rank1(position + 1) * K * K
+    }
+
+    /// Whether the graph contains the edge from `source` to `target`
+    pub fn has_edge(&self, source: usize, target: usize) -> bool {
+        if source >= self.num_nodes || target >= self.num_nodes || self.num_edges == 0 {
+            return false;
+        }
+        let mut children = 0;
+        let mut child_size = self.size / K;
+        loop {
+            let position = children + (source / child_size % K) * K + target / child_size % K;
+            if !self.bit(position) {
+                return false;
+            }
+            if child_size == 1 {
+                return true;
+            }
+            children = self.children(position);
+            child_size /= K;
+        }
+    }
+
+    /// Get the targets of all edges starting at `source` in increasing order
+    pub fn successors(&self, source: usize) -> Vec<usize> {
+        if source >= self.num_nodes {
+            return Vec::new();
+        }
+        self.range(source..source + 1, 0..self.num_nodes).into_iter().map(|(_, target)| target).collect()
+    }
+
+    /// Get the sources of all edges ending at `target` in increasing order
+    pub fn predecessors(&self, target: usize) -> Vec<usize> {
+        if target >= self.num_nodes {
+            return Vec::new();
+        }
+        self.range(0..self.num_nodes, target..target + 1).into_iter().map(|(source, _)| source).collect()
+    }
+
+    /// Get all edges `(source, target)` with `source` in `sources` and `target` in `targets`
+    /// The edges are ordered by their position in the tree, which is increasing if one of the ranges is a single node.
+    pub fn range(&self, sources: Range<usize>, targets: Range<usize>) -> Vec<(usize, usize)> {
+        let mut edges = Vec::new();
+        let sources = sources.start..sources.end.min(self.num_nodes);
+        let targets = targets.start..targets.end.min(self.num_nodes);
+        if self.num_edges > 0 && !sources.is_empty() && !targets.is_empty() {
+            self.range_node(0, self.size / K, 0, 0, &sources, &targets, &mut edges);
+        }
+        edges
+    }
+
+    /// Collect the edges in the children of a node starting at `children`, whose submatrix starts at `row` and `column`
+    #[allow(clippy::too_many_arguments)]
+    fn range_node(&self, children: usize, child_size: usize, row: usize, column: usize, sources: &Range<usize>, targets: &Range<usize>, edges: &mut Vec<(usize, usize)>) {
+        for i in 0..K {
+            let child_row = row + i * child_size;
+            if child_row + child_size <= sources.start || child_row >= sources.end {
+                continue;
+            }
+            for j in 0..K {
+                let child_column = column + j * child_size;
+                if child_column + child_size <= targets.start || child_column >= targets.end {
+                    continue;
+                }
+                let position = children + i * K + j;
+                if !self.bit(position) {
+                    continue;
+                }
+                if child_size == 1 {
+                    edges.push((child_row, child_column));
+                } else {
+                    self.range_node(self.children(position), child_size / K, child_row, child_column, sources, targets, edges);
+                }
+            }
+        }
+    }
+
+    /// Get all edges
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        self.range(0..self.num_nodes, 0..self.num_nodes)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::K2Tree;
+
+    fn test_against_naive(num_nodes: usize, edges: &[(usize, usize)], rng: &mut ChaCha8Rng) {
+        let tree = K2Tree::from_edges(num_nodes, edges);
+        let mut sorted = edges.to_vec();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(tree.num_edges(), sorted.len());
+        let mut all = tree.edges();
+        all.sort();
+        assert_eq!(all, sorted);
+        for node in 0..num_nodes {
+            assert_eq!(tree.successors(node), sorted.iter().filter(|edge| edge.0 == node).map(|edge| edge.1).collect::<Vec<usize>>());
+            let mut predecessors: Vec<usize> = sorted.iter().filter(|edge| edge.1 == node).map(|edge| edge.0).collect();
+            predecessors.sort();
+            assert_eq!(tree.predecessors(node), predecessors);
+        }
+        for _ in 0..200 {
+            let source = rng.gen_range(0..num_nodes);
+            let target = rng.gen_range(0..num_nodes);
+            assert_eq!(tree.has_edge(source, target), sorted.binary_search(&(source, target)).is_ok());
+            let row_start = rng.gen_range(0..=num_nodes);
+            let row_end = rng.gen_range(row_start..=num_nodes);
+            let column_start = rng.gen_range(0..=num_nodes);
+            let column_end = rng.gen_range(column_start..=num_nodes);
+            let mut range = tree.range(row_start..row_end, column_start..column_end);
+            range.sort();
+            let expected: Vec<(usize, usize)> = sorted.iter()
+                .filter(|edge| (row_start..row_end).contains(&edge.0) && (column_start..column_end).contains(&edge.1))
+                .copied().collect();
+            assert_eq!(range, expected);
+        }
+    }
+
+    #[test]
+    fn test_random_graphs() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for (num_nodes, num_edges) in [(1, 1), (2, 3), (7, 10), (100, 500), (1000, 3000)] {
+            let edges: Vec<(usize, usize)> = (0..num_edges).map(|_| (rng.gen_range(0..num_nodes), rng.gen_range(0..num_nodes))).collect();
+            test_against_naive(num_nodes, &edges, &mut rng);
+        }
+    }
+
+    #[test]
+    fn test_clustered_graph() {
+        // edges close to the diagonal share most of their submatrices
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let edges: Vec<(usize, usize)> = (0..5000).map(|_| {
+            let source = rng.gen_range(0..2000);
+            (source, (source + rng.gen_range(0..8)).min(1999))
+        }).collect();
+        test_against_naive(2000, &edges, &mut rng);
+        let tree = K2Tree::from_edges(2000, &edges);
+        assert!(tree.get_size() < edges.len() * 2 * std::mem::size_of::<usize>() / 4);
+    }
+
+    #[test]
+    fn test_empty() {
+        let tree = K2Tree::from_edges(10, &[]);
+        assert_eq!(tree.num_edges(), 0);
+        assert!(!tree.has_edge(1, 2));
+        assert!(tree.successors(3).is_empty());
+        assert!(tree.edges().is_empty());
+        let tree = K2Tree::from_edges(0, &[]);
+        assert!(tree.edges().is_empty());
+    }
+
+    #[test]
+    fn test_out_of_range_node() {
+        let tree = K2Tree::from_edges(10, &[(9, 9), (2, 9)]);
+        for node in [10, usize::MAX] {
+            assert!(tree.successors(node).is_empty());
+            assert!(tree.predecessors(node).is_empty());
+        }
+        assert_eq!(tree.predecessors(9), vec![2, 9]);
+    }
+}
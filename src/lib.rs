@@ -1,11 +1,58 @@
+use std::cmp::min;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::mem::size_of;
-use std::ops::Range;
+use std::ops::{Index, Range};
+use std::sync::OnceLock;
 use crate::rank::RankAccelerator;
 use crate::select::SelectAccelerator;
 
 mod rank;
 mod select;
 mod select_table;
+mod indexed;
+mod sparse;
+mod rrr;
+mod run_length;
+mod traits;
+mod adaptive;
+mod wavelet_tree;
+mod wavelet_matrix;
+mod balanced_parens;
+mod louds;
+mod k2_tree;
+mod fm_index;
+mod int_vector;
+mod dac;
+mod prefix_sums;
+mod dynamic;
+mod dna;
+mod sparse_vec;
+mod rmq;
+mod mphf;
+#[cfg(test)]
+mod test_util;
+
+pub use traits::{BitAccess, Rank, RankSelect, Select0, Select1, SpaceUsage};
+pub use indexed::IndexedBitVector;
+pub use sparse::SparseBitVector;
+pub use rrr::RrrBitVector;
+pub use run_length::RunLengthBitVector;
+pub use adaptive::{AdaptiveBitVector, Representation};
+pub use wavelet_tree::{Symbol, WaveletTree};
+pub use wavelet_matrix::{PointGrid, WaveletMatrix};
+pub use balanced_parens::BalancedParens;
+pub use louds::Louds;
+pub use k2_tree::K2Tree;
+pub use fm_index::FmIndex;
+pub use int_vector::IntVector;
+pub use dac::DacVector;
+pub use prefix_sums::{EliasFanoPrefixSums, PrefixSums};
+pub use dynamic::DynamicBitVector;
+pub use dna::{DnaRank, InvalidBase};
+pub use sparse_vec::SparseVec;
+pub use rmq::Rmq;
+pub use mphf::Mphf;
 
 /// The base type can be changed using features
 #[cfg(feature = "UNIT_U8")]
@@ -21,7 +68,18 @@ type Unit = usize;
 
 const UNIT_SIZE_BITS: usize = Unit::BITS as usize;
 
+/// The number of bits shown by `Debug` before the preview is truncated
+const DEBUG_PREVIEW_BITS: usize = 64;
+
 /// A bit vector that supports fast rank and select
+///
+/// The accelerator structures are built lazily by the first query that needs them.
+/// Construction is synchronized, so a `BitVector` can be shared between threads (e.g. behind an `Arc`)
+/// and queried concurrently without a separate init phase.
+///
+/// Cloning also clones the accelerators which were already created.
+/// Equality and hashing only consider the bits and not which accelerators exist.
+#[derive(Clone)]
 pub struct BitVector {
     /// The raw bitvector data
     data: Vec<Unit>,
@@ -29,12 +87,12 @@ pub struct BitVector {
     len: usize,
 
     /// Used to accelerate rank operations
-    rank_accelerator: Option<RankAccelerator>,
+    rank_accelerator: OnceLock<RankAccelerator>,
 
     /// Used to accelerate zero select operations
-    select_accelerator_0: Option<SelectAccelerator<false>>,
+    select_accelerator_0: OnceLock<SelectAccelerator<false>>,
     /// Used to accelerate one select operations
-    select_accelerator_1: Option<SelectAccelerator<true>>
+    select_accelerator_1: OnceLock<SelectAccelerator<true>>
 }
 
 impl Default for BitVector {
@@ -44,15 +102,133 @@ impl Default for BitVector {
     }
 }
 
+impl PartialEq for BitVector {
+    /// Two bit vectors are equal if they contain the same bits
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.words() == other.words()
+    }
+}
+
+impl Eq for BitVector {}
+
+impl Hash for BitVector {
+    /// Hashes the bits of the bit vector
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        self.words().hash(state);
+    }
+}
+
+impl Index<usize> for BitVector {
+    type Output = bool;
+
+    /// Get the bit at `index`
+    fn index(&self, index: usize) -> &Self::Output {
+        if index >= self.len {
+            panic!("index out of bounds: the len is {} but the index is {index}", self.len);
+        }
+        if self.access(index) == 1 { &true } else { &false }
+    }
+}
+
+impl Extend<bool> for BitVector {
+    /// Append the bits of `iter`, updating existing accelerators like `push`
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        for bit in iter {
+            self.push(bit);
+        }
+    }
+}
+
+impl FromIterator<bool> for BitVector {
+    /// Creates a BitVector without initializing any accelerator structures from the bits of `iter`
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bit_vector = Self::new();
+        let mut tmp = 0;
+        for b in iter {
+            // every UNIT_SIZE_BITS push tmp into the raw data vector
+            if bit_vector.len != 0 && bit_vector.len.is_multiple_of(UNIT_SIZE_BITS) {
+                bit_vector.data.push(tmp);
+                tmp = 0;
+            }
+            if b {
+                tmp |= 1 << (bit_vector.len % UNIT_SIZE_BITS);
+            }
+            bit_vector.len += 1;
+        }
+        if bit_vector.len != 0 {
+            bit_vector.data.push(tmp);
+        }
+        // shrink to fit the data
+        bit_vector.data.shrink_to_fit();
+        bit_vector
+    }
+}
+
+impl fmt::Debug for BitVector {
+    /// Shows the length, the density of ones and the first `DEBUG_PREVIEW_BITS` bits
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let density = if self.is_empty() { 0f64 } else { self.count_ones(0..self.len) as f64 / self.len as f64 };
+        let mut bits: String = (0..self.len.min(DEBUG_PREVIEW_BITS)).map(|i| if self.access(i) == 1 { '1' } else { '0' }).collect();
+        if self.len > DEBUG_PREVIEW_BITS {
+            bits += "...";
+        }
+        f.debug_struct("BitVector")
+            .field("len", &self.len)
+            .field("density", &density)
+            .field("bits", &bits)
+            .finish()
+    }
+}
+
+impl fmt::Display for BitVector {
+    /// Writes one '0' or '1' per bit, so the output can be loaded using `load_from_string`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Binary::fmt(self, f)
+    }
+}
+
+impl fmt::Binary for BitVector {
+    /// Writes one '0' or '1' per bit, so the output can be loaded using `load_from_string`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0b")?;
+        }
+        for i in 0..self.len {
+            f.write_str(if self.access(i) == 1 { "1" } else { "0" })?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::LowerHex for BitVector {
+    /// Writes one hex digit per four bits.
+    /// The digits are in the same order as the bits, and inside a digit the first bit is the least significant one.
+    /// This matches the order in which the bits are stored in memory.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        for i in (0..self.len).step_by(4) {
+            let mut nibble = 0;
+            for j in i..(i + 4).min(self.len) {
+                nibble |= self.access(j) << (j - i);
+            }
+            write!(f, "{nibble:x}")?;
+        }
+        Ok(())
+    }
+}
+
 impl BitVector {
     /// Creates an empty bit vector
     pub fn new() -> Self {
         BitVector {
             data: Vec::new(),
             len: 0,
-            rank_accelerator: None,
-            select_accelerator_0: None,
-            select_accelerator_1: None
+            rank_accelerator: OnceLock::new(),
+            select_accelerator_0: OnceLock::new(),
+            select_accelerator_1: OnceLock::new()
         }
     }
 
@@ -61,9 +237,14 @@ impl BitVector {
         self.data.capacity() * size_of::<Unit>() + self.get_size_rank() + self.get_size_select_0() + self.get_size_select_1()
     }
 
+    /// Estimate the size of a bit vector with `len` bits and no accelerators without building it
+    pub(crate) fn estimate_size(len: usize) -> usize {
+        len.div_ceil(UNIT_SIZE_BITS) * size_of::<Unit>()
+    }
+
     /// Get the size of the rank accelerator including space on the heap
     pub fn get_size_rank(&self) -> usize {
-        if let Some(rank_accelerator) = &self.rank_accelerator {
+        if let Some(rank_accelerator) = self.rank_accelerator.get() {
             rank_accelerator.get_size()
         } else {
             0
@@ -72,7 +253,7 @@ impl BitVector {
 
     /// Get the size of the select zero accelerator including space on the heap
     pub fn get_size_select_0(&self) -> usize {
-        if let Some(select_accelerator_0) = &self.select_accelerator_0 {
+        if let Some(select_accelerator_0) = self.select_accelerator_0.get() {
             select_accelerator_0.get_size()
         } else {
             0
@@ -81,7 +262,7 @@ impl BitVector {
 
     /// Get the size of the select one accelerator including space on the heap
     pub fn get_size_select_1(&self) -> usize {
-        if let Some(select_accelerator_1) = &self.select_accelerator_1 {
+        if let Some(select_accelerator_1) = self.select_accelerator_1.get() {
             select_accelerator_1.get_size()
         } else {
             0
@@ -115,28 +296,66 @@ impl BitVector {
     }
 
     /// Creates the rank accelerator
+    /// Calling this is optional because the accelerator is created by the first rank query otherwise.
+    /// An already existing accelerator is rebuilt.
     pub fn init_rank_structures(&mut self) {
-        let mut rank_accelerator = RankAccelerator::new();
-        rank_accelerator.init(self);
-        self.rank_accelerator = Some(rank_accelerator);
+        self.rank_accelerator = OnceLock::from(self.build_rank_accelerator());
     }
 
     /// Creates the select accelerators
+    /// Calling this is optional because the accelerators are created by the first select query otherwise.
+    /// Already existing accelerators are rebuilt.
     pub fn init_select_structures(&mut self) {
-        let mut select_accelerator_0 = SelectAccelerator::new();
-        select_accelerator_0.init(self);
-        self.select_accelerator_0 = Some(select_accelerator_0);
-        let mut select_accelerator_1 = SelectAccelerator::new();
-        select_accelerator_1.init(self);
-        self.select_accelerator_1 = Some(select_accelerator_1);
+        self.select_accelerator_0 = OnceLock::from(self.build_select_accelerator());
+        self.select_accelerator_1 = OnceLock::from(self.build_select_accelerator());
     }
 
     /// Initializes accelerators structures
+    /// Calling this is optional because the accelerators are created by the first query otherwise.
     pub fn init(&mut self) {
         self.init_rank_structures();
         self.init_select_structures();
     }
 
+    /// Get the words of `data` which contain bits of the vector
+    #[inline]
+    fn words(&self) -> &[Unit] {
+        &self.data[..self.len.div_ceil(UNIT_SIZE_BITS)]
+    }
+
+    /// Builds a new rank accelerator for the current data
+    fn build_rank_accelerator(&self) -> RankAccelerator {
+        let mut rank_accelerator = RankAccelerator::new();
+        rank_accelerator.init(self);
+        rank_accelerator
+    }
+
+    /// Builds a new zero/one select accelerator for the current data
+    fn build_select_accelerator<const BIT: bool>(&self) -> SelectAccelerator<BIT> {
+        let mut select_accelerator = SelectAccelerator::new();
+        select_accelerator.init(self);
+        select_accelerator
+    }
+
+    /// Get the rank accelerator, creating it if this is the first rank query
+    /// If several threads query at the same time the accelerator is still only created once.
+    #[inline]
+    fn rank_accelerator(&self) -> &RankAccelerator {
+        self.rank_accelerator.get_or_init(|| self.build_rank_accelerator())
+    }
+
+    /// Get the zero select accelerator, creating it if this is the first zero select query
+    #[inline]
+    fn select_accelerator_0(&self) -> &SelectAccelerator<false> {
+        self.select_accelerator_0.get_or_init(|| self.build_select_accelerator())
+    }
+
+    /// Get the one select accelerator, creating it if this is the first one select query
+    #[inline]
+    fn select_accelerator_1(&self) -> &SelectAccelerator<true> {
+        self.select_accelerator_1.get_or_init(|| self.build_select_accelerator())
+    }
+
     /// Get the length of the vector
     #[inline]
     pub fn len(&self) -> usize {
@@ -151,6 +370,8 @@ impl BitVector {
 
     /// Get the bit at `index`
     #[inline]
+    // the cast is only a no-op if `Unit` is `usize`
+    #[allow(clippy::unnecessary_cast)]
     pub fn access(&self, index: usize) -> usize {
         // calculate the word index
         let vec_index = index / UNIT_SIZE_BITS;
@@ -175,52 +396,214 @@ impl BitVector {
         lower | upper
     }
 
-    /// Get the number of one bits in the `range`
+    /// Get the `width` <= 64 bits starting at `index` as an integer
+    /// The bit at `index` becomes the least significant bit.
     #[inline]
-    pub fn count_ones(&self, range: Range<usize>) -> usize {
+    pub fn get_bits(&self, index: usize, width: usize) -> u64 {
+        assert!(width <= u64::BITS as usize, "width {width} is larger than 64");
+        assert!(index + width <= self.len, "bits {index}..{} are out of bounds for length {}", index + width, self.len);
         let mut result = 0;
-        let blocks: Vec<Unit> = range.clone().step_by(UNIT_SIZE_BITS).map(|i| self.access_block(i)).collect();
-        
-        // Count all blocks that are fully container in the range efficiently using count_ones
-        for block in blocks.iter().take(blocks.len() - 1) {
-            result += block.count_ones() as usize;
-        }
-        // calculate a bit maks to count the ones in the last block which maybe only partial in the range
-        let mask = if (range.end - range.start) % UNIT_SIZE_BITS == 0 {
-            usize::MAX
+        let mut read = 0;
+        // `access_block` returns at most `UNIT_SIZE_BITS` bits, so wide reads are split up
+        while read < width {
+            let chunk = min(UNIT_SIZE_BITS, width - read);
+            let mask = if chunk == u64::BITS as usize { u64::MAX } else { (1 << chunk) - 1 };
+            result |= (self.access_block(index + read) as u64 & mask) << read;
+            read += chunk;
+        }
+        result
+    }
+
+    /// Set the `width` <= 64 bits starting at `index` to the bits of `value`
+    /// The least significant bit of `value` is written to `index`, like `get_bits` reads it.
+    /// Existing accelerators are dropped, as they do not match the new bits anymore.
+    // the casts are only no-ops if `Unit` is `u64` or `usize`
+    #[allow(clippy::unnecessary_cast)]
+    pub fn set_bits(&mut self, index: usize, width: usize, value: u64) {
+        assert!(width <= u64::BITS as usize, "width {width} is larger than 64");
+        assert!(index + width <= self.len, "bits {index}..{} are out of bounds for length {}", index + width, self.len);
+        assert!(width == u64::BITS as usize || value >> width == 0, "value {value} does not fit into {width} bits");
+        self.invalidate();
+        let mut written = 0;
+        // every iteration writes the part of `value` inside one word
+        while written < width {
+            let position = index + written;
+            let shift = position % UNIT_SIZE_BITS;
+            let chunk = min(UNIT_SIZE_BITS - shift, width - written);
+            let mask = if chunk == u64::BITS as usize { u64::MAX } else { (1 << chunk) - 1 };
+            let word = &mut self.data[position / UNIT_SIZE_BITS];
+            *word = (*word & !((mask as Unit) << shift)) | ((((value >> written) & mask) as Unit) << shift);
+            written += chunk;
+        }
+    }
+
+    /// Set the bit at `index`
+    /// Existing accelerators are dropped, as they do not match the new bits anymore.
+    pub fn set(&mut self, index: usize, bit: bool) {
+        self.set_bits(index, 1, bit as u64);
+    }
+
+    /// Append zeros or drop bits at the end until the vector has `len` bits
+    /// Existing accelerators are dropped, as they do not match the new bits anymore.
+    pub fn resize(&mut self, len: usize) {
+        self.invalidate();
+        if len < self.len {
+            // bits after the end of the vector have to be zero
+            let end = len % UNIT_SIZE_BITS;
+            self.data.truncate(len.div_ceil(UNIT_SIZE_BITS));
+            if end != 0 {
+                *self.data.last_mut().unwrap() &= (1 << end) - 1;
+            }
         } else {
-            (1 << ((range.end - range.start) % UNIT_SIZE_BITS)) - 1
-        };
-        let last_block = blocks.last().unwrap();
-        let remaining = (last_block & (mask as Unit)).count_ones() as usize;
-        result + remaining
+            self.data.resize(len.div_ceil(UNIT_SIZE_BITS), 0);
+        }
+        self.len = len;
+    }
+
+    /// Append `bit` to the end
+    /// Existing accelerators are updated instead of dropped, which takes amortised constant time.
+    /// Accelerators which do not exist yet are still created by the first query which needs them.
+    pub fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(UNIT_SIZE_BITS) {
+            self.data.push(0);
+        }
+        if bit {
+            self.data[self.len / UNIT_SIZE_BITS] |= 1 << (self.len % UNIT_SIZE_BITS);
+        }
+        self.len += 1;
+        // the accelerators are taken out, so they can read the vector while they are updated
+        if let Some(mut rank_accelerator) = self.rank_accelerator.take() {
+            rank_accelerator.push(self);
+            self.rank_accelerator = OnceLock::from(rank_accelerator);
+        }
+        if let Some(mut select_accelerator) = self.select_accelerator_0.take() {
+            select_accelerator.push(self);
+            self.select_accelerator_0 = OnceLock::from(select_accelerator);
+        }
+        if let Some(mut select_accelerator) = self.select_accelerator_1.take() {
+            select_accelerator.push(self);
+            self.select_accelerator_1 = OnceLock::from(select_accelerator);
+        }
+    }
+
+    /// Drop all accelerators, they are rebuilt by the next query which needs them
+    fn invalidate(&mut self) {
+        self.rank_accelerator.take();
+        self.select_accelerator_0.take();
+        self.select_accelerator_1.take();
+    }
+
+    /// Iterate over the runs of equal bits as `(bit, start, length)`
+    /// Whole words are skipped at once, so long runs are cheap.
+    pub fn runs(&self) -> impl Iterator<Item = (bool, usize, usize)> + '_ {
+        let mut start = 0;
+        std::iter::from_fn(move || {
+            if start >= self.len {
+                return None;
+            }
+            let bit = self.access(start) == 1;
+            let mut end = start;
+            loop {
+                // invert the block when looking for ones, so the run is always the trailing zeros
+                let block = if bit { !self.access_block(end) } else { self.access_block(end) };
+                let run = block.trailing_zeros() as usize;
+                end += run;
+                if run < UNIT_SIZE_BITS || end >= self.len {
+                    break;
+                }
+            }
+            // the bits after the end of the vector are zeros and may have extended a run of zeros
+            let end = min(end, self.len);
+            let run = (bit, start, end - start);
+            start = end;
+            Some(run)
+        })
+    }
+
+    /// Get the number of one bits in the `range`
+    #[inline]
+    pub fn count_ones(&self, range: Range<usize>) -> usize {
+        if range.is_empty() {
+            return 0;
+        }
+        let len = range.end - range.start;
+        // count all blocks that are fully contained in the range, without collecting them first
+        let full_blocks = len / UNIT_SIZE_BITS;
+        let mut result: usize = (0..full_blocks)
+            .map(|block| self.access_block(range.start + block * UNIT_SIZE_BITS).count_ones() as usize)
+            .sum();
+        // the last block may be only partially in the range
+        let remaining = len % UNIT_SIZE_BITS;
+        if remaining > 0 {
+            let mask: Unit = (1 << remaining) - 1;
+            result += (self.access_block(range.start + full_blocks * UNIT_SIZE_BITS) & mask).count_ones() as usize;
+        }
+        result
+    }
+
+    /// Get the number of zeros before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    #[inline]
+    pub fn rank0(&self, index: usize) -> usize {
+        self.rank_accelerator().rank0(index, self)
+    }
+
+    /// Get the number of ones before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    /// For every `k` less than the number of ones `rank1(select1(k)) == k` holds.
+    #[inline]
+    pub fn rank1(&self, index: usize) -> usize {
+        self.rank_accelerator().rank1(index, self)
+    }
+
+    /// Get the number of zero/one's up to and including `index`, i.e. in `0..=index`
+    #[inline]
+    pub fn rank_inclusive(&self, bit: bool, index: usize) -> usize {
+        if bit { self.rank1(index + 1) } else { self.rank0(index + 1) }
+    }
+
+    /// Get the position of the `index`-th zero, counting from zero
+    /// Returns `None` if the vector contains at most `index` zeros.
+    /// For every `k` less than the number of zeros `rank0(select0(k)) == k` holds.
+    #[inline]
+    pub fn select0(&self, index: usize) -> Option<usize> {
+        self.select_accelerator_0().select(index, self)
+    }
+
+    /// Get the position of the `index`-th one, counting from zero
+    /// Returns `None` if the vector contains at most `index` ones.
+    /// For every `k` less than the number of ones `rank1(select1(k)) == k` holds.
+    #[inline]
+    pub fn select1(&self, index: usize) -> Option<usize> {
+        self.select_accelerator_1().select(index, self)
     }
 
     /// Get the number of zero/one's before `index`
     #[inline]
+    #[deprecated(note = "use `rank0` or `rank1` instead")]
     pub fn rank(&self, bit: bool, index: usize) -> usize {
-        self.rank_accelerator.as_ref().expect("Rank acceleration structures not initialized!").rank(bit, index, self)
+        if bit { self.rank1(index) } else { self.rank0(index) }
     }
 
-    /// Get the position of the `index`-th zero/one
+    /// Get the position of the `index`-th zero/one, counting from one
     #[inline]
+    #[deprecated(note = "use the zero based `select0` or `select1` instead")]
     pub fn select(&self, bit: bool, index: usize) -> usize {
-        // index -1 because select_accelerator is zero based
-        if bit {
-            self.select_accelerator_1.as_ref().expect("Select acceleration structures not initialized!").select(index-1, self)
-        } else {
-            self.select_accelerator_0.as_ref().expect("Select acceleration structures not initialized!").select(index-1, self)
-        }
+        // index -1 because select0 and select1 are zero based
+        if bit { self.select1(index-1) } else { self.select0(index-1) }.expect("No ith zero/one found")
     }
 }
 
 #[cfg(test)]
 pub mod test {
     use std::cmp::min;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
     use rand::Rng;
     use rand::SeedableRng;
     use rand_chacha::ChaCha8Rng;
-    use crate::{BitVector, UNIT_SIZE_BITS};
+    use std::hash::{Hash, Hasher};
+    use crate::{BitVector, DEBUG_PREVIEW_BITS, UNIT_SIZE_BITS};
 
     #[test]
     fn test_load_from_string_and_access() {
@@ -252,6 +635,8 @@ pub mod test {
         test_rank(&data);
     }
 
+    // tests the deprecated one based API
+    #[allow(deprecated)]
     fn test_rank(data: &str) {
         let mut bit_vector = BitVector::load_from_string(data);
         bit_vector.init_rank_structures();
@@ -284,6 +669,8 @@ pub mod test {
     }
     
 
+    // tests the deprecated one based API
+    #[allow(deprecated)]
     fn test_select(data: &str) {
         let mut bit_vector = BitVector::load_from_string(data);
         bit_vector.init_select_structures();
@@ -327,4 +714,353 @@ pub mod test {
         }
         assert_eq!(zeroes, bit_vector.count_ones(start..end));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_lazy_init() {
+        let data = "010010001010100001111010101011111001000010111000111000110101010011010100101010111110000110101101010101111101010101110000111011100110110101110101111";
+        let bit_vector = BitVector::load_from_string(data);
+        assert!(bit_vector.rank_accelerator.get().is_none());
+        assert!(bit_vector.select_accelerator_0.get().is_none());
+        assert!(bit_vector.select_accelerator_1.get().is_none());
+        // the first query only creates the accelerator it needs
+        bit_vector.rank1(10);
+        assert!(bit_vector.rank_accelerator.get().is_some());
+        assert!(bit_vector.select_accelerator_0.get().is_none());
+        bit_vector.select1(0);
+        assert!(bit_vector.select_accelerator_0.get().is_none());
+        assert!(bit_vector.select_accelerator_1.get().is_some());
+    }
+
+    #[test]
+    fn test_concurrent_first_queries() {
+        const THREADS: usize = 8;
+        let mut data = String::new();
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for _ in 0..65536 {
+            if rng.gen_range(0..=1) == 0 {
+                data += "0";
+            } else {
+                data += "1";
+            }
+        }
+        let bit_vector = Arc::new(BitVector::load_from_string(&data));
+        // all threads wait at the barrier, so their first queries race against each other
+        let barrier = Arc::new(Barrier::new(THREADS));
+        let handles: Vec<_> = (0..THREADS).map(|t| {
+            let bit_vector = Arc::clone(&bit_vector);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                let mut ones = 0;
+                for i in (t..bit_vector.len()).step_by(THREADS * 7) {
+                    ones = bit_vector.rank1(i);
+                    assert_eq!(bit_vector.rank0(i), i - ones);
+                    if bit_vector.access(i) == 1 {
+                        assert_eq!(bit_vector.select1(ones), Some(i));
+                    } else {
+                        assert_eq!(bit_vector.select0(i - ones), Some(i));
+                    }
+                }
+                // return the addresses of the accelerators this thread used
+                (ones,
+                 bit_vector.rank_accelerator.get().unwrap() as *const _ as usize,
+                 bit_vector.select_accelerator_0.get().unwrap() as *const _ as usize,
+                 bit_vector.select_accelerator_1.get().unwrap() as *const _ as usize)
+            })
+        }).collect();
+        let results: Vec<_> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+        // every thread has to see the same, single instance of each accelerator
+        for result in &results {
+            assert_eq!(result.1, results[0].1);
+            assert_eq!(result.2, results[0].2);
+            assert_eq!(result.3, results[0].3);
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BitVector>();
+    }
+
+    fn test_rank_select_relationship(data: &str) {
+        let bit_vector = BitVector::load_from_string(data);
+        let ones = bit_vector.count_ones(0..bit_vector.len());
+        let zeros = bit_vector.len() - ones;
+        assert_eq!(bit_vector.rank1(bit_vector.len()), ones);
+        assert_eq!(bit_vector.rank0(bit_vector.len()), zeros);
+        for k in 0..ones {
+            let position = bit_vector.select1(k).unwrap();
+            assert_eq!(bit_vector.access(position), 1);
+            assert_eq!(bit_vector.rank1(position), k);
+            assert_eq!(bit_vector.rank_inclusive(true, position), k + 1);
+        }
+        for k in 0..zeros {
+            let position = bit_vector.select0(k).unwrap();
+            assert_eq!(bit_vector.access(position), 0);
+            assert_eq!(bit_vector.rank0(position), k);
+            assert_eq!(bit_vector.rank_inclusive(false, position), k + 1);
+        }
+        assert_eq!(bit_vector.select1(ones), None);
+        assert_eq!(bit_vector.select0(zeros), None);
+    }
+
+    #[test]
+    fn test_rank_select_relationship_small() {
+        let data = "010010001010100001111010101011111001000010111000111000110101010011010100101010111110000110101101010101111101010101110000111011100110110101110101111";
+        test_rank_select_relationship(data);
+        // a length that is a multiple of the word size
+        test_rank_select_relationship(&data[..128]);
+    }
+
+    #[test]
+    fn test_rank_select_relationship_large() {
+        let mut data = String::new();
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for _ in 0..131072 {
+            if rng.gen_range(0..=3) == 0 {
+                data += "0";
+            } else {
+                data += "1";
+            }
+        }
+        test_rank_select_relationship(&data);
+    }
+
+    #[test]
+    fn test_clone() {
+        let data = "010010001010100001111010101011111001000010111000111000110101010011010100101010111110000110101101010101111101010101110000111011100110110101110101111";
+        let bit_vector = BitVector::load_from_string(data);
+        bit_vector.rank1(10);
+        let clone = bit_vector.clone();
+        // the existing accelerator is cloned, the missing ones are still missing
+        assert_eq!(clone.get_size_rank(), bit_vector.get_size_rank());
+        assert_eq!(clone.get_size_select_1(), 0);
+        assert_eq!(clone, bit_vector);
+        for i in 0..=data.len() {
+            assert_eq!(clone.rank1(i), bit_vector.rank1(i));
+        }
+    }
+
+    #[test]
+    fn test_eq_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        fn hash(bit_vector: &BitVector) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            bit_vector.hash(&mut hasher);
+            hasher.finish()
+        }
+        let a = BitVector::load_from_string("0100100010101");
+        let b = BitVector::load_from_string("0100100010101");
+        b.select0(0);
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        // same bits but different length
+        assert_ne!(a, BitVector::load_from_string("01001000101010"));
+        assert_ne!(a, BitVector::load_from_string("0100100010100"));
+        assert_eq!(BitVector::new(), BitVector::load_from_string(""));
+        assert_eq!(hash(&BitVector::new()), hash(&BitVector::load_from_string("")));
+    }
+
+    #[test]
+    fn test_fmt() {
+        let data = "010010001010100001111010101011111001000010111000111000110101010011010100101010111110000110101101010101111101010101110000111011100110110101110101111";
+        let bit_vector = BitVector::load_from_string(data);
+        assert_eq!(bit_vector.to_string(), data);
+        assert_eq!(format!("{bit_vector:b}"), data);
+        assert_eq!(format!("{bit_vector:#b}"), format!("0b{data}"));
+        assert_eq!(BitVector::load_from_string(&bit_vector.to_string()), bit_vector);
+        assert_eq!(format!("{:x}", BitVector::load_from_string("100011110000011")), "1f06");
+        assert_eq!(format!("{:#x}", BitVector::load_from_string("0101")), "0xa");
+        let debug = format!("{bit_vector:?}");
+        assert!(debug.contains(&format!("len: {}", data.len())));
+        assert!(debug.contains(&format!("{}...", &data[..DEBUG_PREVIEW_BITS])));
+        assert_eq!(format!("{:?}", BitVector::load_from_string("0011")), "BitVector { len: 4, density: 0.5, bits: \"0011\" }");
+    }
+
+    #[test]
+    fn test_index() {
+        let data = "0100100010101000011110101";
+        let bit_vector = BitVector::load_from_string(data);
+        for (i, c) in data.chars().enumerate() {
+            assert_eq!(bit_vector[i], c == '1');
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_bounds() {
+        let bit_vector = BitVector::load_from_string("0100");
+        let _ = bit_vector[4];
+    }
+
+    /// Checks all queries of `bit_vector` against a naive implementation using `bits`
+    fn test_against_naive(bit_vector: &BitVector, bits: &[bool]) {
+        assert_eq!(bit_vector.len(), bits.len());
+        assert_eq!(bit_vector.is_empty(), bits.is_empty());
+        let mut ones = Vec::new();
+        let mut zeros = Vec::new();
+        for (i, &b) in bits.iter().enumerate() {
+            assert_eq!(bit_vector.access(i) == 1, b);
+            assert_eq!(bit_vector.rank1(i), ones.len());
+            assert_eq!(bit_vector.rank0(i), zeros.len());
+            if b { ones.push(i) } else { zeros.push(i) }
+        }
+        assert_eq!(bit_vector.rank1(bits.len()), ones.len());
+        assert_eq!(bit_vector.rank0(bits.len()), zeros.len());
+        assert_eq!(bit_vector.count_ones(0..bits.len()), ones.len());
+        for (k, &position) in ones.iter().enumerate() {
+            assert_eq!(bit_vector.select1(k), Some(position));
+        }
+        for (k, &position) in zeros.iter().enumerate() {
+            assert_eq!(bit_vector.select0(k), Some(position));
+        }
+        assert_eq!(bit_vector.select1(ones.len()), None);
+        assert_eq!(bit_vector.select0(zeros.len()), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        test_against_naive(&BitVector::new(), &[]);
+        test_against_naive(&BitVector::default(), &[]);
+        test_against_naive(&BitVector::load_from_string(""), &[]);
+        let mut bit_vector = BitVector::new();
+        bit_vector.init();
+        test_against_naive(&bit_vector, &[]);
+        test_against_naive(BitVector::new().build().as_bit_vector(), &[]);
+        assert_eq!(BitVector::new().build().select1(0), None);
+        assert_eq!(BitVector::new().count_ones(0..0), 0);
+    }
+
+    #[test]
+    fn test_all_small_vectors() {
+        // every vector with up to 16 bits
+        for len in 0..=16 {
+            for value in 0..(1u32 << len) {
+                let bits: Vec<bool> = (0..len).map(|i| (value >> i) & 1 == 1).collect();
+                let data: String = bits.iter().map(|&b| if b { '1' } else { '0' }).collect();
+                test_against_naive(&BitVector::load_from_string(&data), &bits);
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let data = "010010001010100001111010101011111001000010111000111000110101010011010100101010111110000110101101010101111101010101110000111011100110110101110101111";
+        for len in [0, 1, 63, 64, 65, 128, data.len()] {
+            let bit_vector: BitVector = data[..len].chars().map(|c| c == '1').collect();
+            assert_eq!(bit_vector, BitVector::load_from_string(&data[..len]));
+            assert_eq!(bit_vector.to_string(), &data[..len]);
+        }
+    }
+
+    #[test]
+    fn test_get_bits() {
+        let data = "010010001010100001111010101011111001000010111000111000110101010011010100101010111110000110101101010101111101010101110000111011100110110101110101111";
+        let bit_vector = BitVector::load_from_string(data);
+        for width in [0, 1, 7, 33, 64] {
+            for index in 0..=(data.len() - width) {
+                let expected = (0..width).fold(0u64, |value, j| value | (bit_vector.access(index + j) as u64) << j);
+                assert_eq!(bit_vector.get_bits(index, width), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_bits() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let mut bits: Vec<bool> = (0..500).map(|_| rng.gen_range(0..2) == 1).collect();
+        let mut bit_vector: BitVector = bits.iter().copied().collect();
+        bit_vector.init();
+        for width in [0, 1, 7, 33, 64] {
+            for _ in 0..50 {
+                let index = rng.gen_range(0..=bits.len() - width);
+                let value = if width == 64 { rng.gen::<u64>() } else { rng.gen_range(0..1u64 << width) };
+                bit_vector.set_bits(index, width, value);
+                for j in 0..width {
+                    bits[index + j] = (value >> j) & 1 == 1;
+                }
+                assert_eq!(bit_vector.get_bits(index, width), value);
+            }
+        }
+        assert_eq!(bit_vector, bits.iter().copied().collect());
+        // the accelerators are rebuilt for the new bits
+        assert_eq!(bit_vector.get_size_rank(), 0);
+        assert_eq!(bit_vector.rank1(bits.len()), bits.iter().filter(|&&bit| bit).count());
+        bit_vector.set(3, !bits[3]);
+        assert_eq!(bit_vector[3], !bits[3]);
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut bit_vector = BitVector::load_from_string("1111111111");
+        bit_vector.resize(3);
+        assert_eq!(bit_vector, BitVector::load_from_string("111"));
+        bit_vector.resize(200);
+        assert_eq!(bit_vector.rank1(200), 3);
+        assert_eq!(bit_vector.len(), 200);
+    }
+
+    #[test]
+    fn test_push() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let mut bit_vector = BitVector::new();
+        bit_vector.init();
+        let mut ones = Vec::new();
+        let mut zeros = Vec::new();
+        for i in 0..3000 {
+            // sparse ones make the one select super blocks span many bits
+            let bit = if i < 1500 { rng.gen_range(0..8) == 0 } else { rng.gen_range(0..2) == 0 };
+            bit_vector.push(bit);
+            if bit { ones.push(i) } else { zeros.push(i) }
+            // the accelerators must be valid after every push
+            let index = rng.gen_range(0..=bit_vector.len());
+            assert_eq!(bit_vector.rank1(index), ones.partition_point(|&one| one < index));
+            for k in [0, ones.len() / 2, ones.len().saturating_sub(1), ones.len()] {
+                assert_eq!(bit_vector.select1(k), ones.get(k).copied());
+            }
+            for k in [0, zeros.len() / 2, zeros.len().saturating_sub(1), zeros.len()] {
+                assert_eq!(bit_vector.select0(k), zeros.get(k).copied());
+            }
+        }
+        // compare with the accelerators of a vector built at once
+        let fresh: BitVector = (0..bit_vector.len()).map(|i| bit_vector[i]).collect();
+        assert_eq!(bit_vector, fresh);
+        for index in 0..=bit_vector.len() {
+            assert_eq!(bit_vector.rank1(index), fresh.rank1(index));
+        }
+        for k in 0..=ones.len() {
+            assert_eq!(bit_vector.select1(k), fresh.select1(k));
+        }
+        for k in 0..=zeros.len() {
+            assert_eq!(bit_vector.select0(k), fresh.select0(k));
+        }
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut bit_vector = BitVector::load_from_string("0110");
+        bit_vector.init();
+        bit_vector.extend("1001110001".chars().map(|c| c == '1'));
+        assert_eq!(bit_vector, BitVector::load_from_string("01101001110001"));
+        assert_eq!(bit_vector.rank1(14), 7);
+        assert_eq!(bit_vector.select0(6), Some(12));
+        assert_eq!(bit_vector.select1(6), Some(13));
+    }
+
+    #[test]
+    fn test_runs() {
+        let data = "0000000000000000000000000000000000000000000000000000000000000000000000001111111111111111111111111111111111111111111111111111111111111111111111110100110";
+        for len in [0, 1, 5, 64, 72, 100, 128, 144, data.len()] {
+            let bit_vector = BitVector::load_from_string(&data[..len]);
+            let mut expected = Vec::new();
+            for (i, c) in data[..len].chars().enumerate() {
+                match expected.last_mut() {
+                    Some((bit, _, length)) if *bit == (c == '1') => *length += 1,
+                    _ => expected.push((c == '1', i, 1)),
+                }
+            }
+            assert_eq!(bit_vector.runs().collect::<Vec<_>>(), expected);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+use std::mem::size_of;
+use crate::BitVector;
+
+/// A tree in level-order unary degree sequence (LOUDS) representation using about two bits per node
+/// The bits start with `10` for a virtual super root, followed by the degree of every node in level order,
+/// each encoded in unary as ones terminated by a zero.
+/// Nodes are numbered in level order starting with the root at zero.
+/// Then, the `i`-th one represents node `i` and the children of node `i` are the ones after the `i`-th zero.
+pub struct Louds {
+    /// The unary encoded degrees with rank and select structures
+    bits: BitVector,
+}
+
+impl Louds {
+    /// Creates a tree from unary encoded degrees in level order, preceded by `10` for the super root
+    /// Panics if the bits do not describe a tree.
+    pub fn new(mut bits: BitVector) -> Self {
+        bits.init();
+        let ones = bits.rank1(bits.len());
+        assert!(bits.len() >= 2 && bits.access(0) == 1 && bits.access(1) == 0, "LOUDS has to start with 10");
+        assert_eq!(bits.len(), 2 * ones + 1, "The degrees do not describe a tree");
+        // every node has to be referenced by a one before the zero terminating its children
+        let mut nodes = 0;
+        let mut zeros = 0;
+        for i in 0..bits.len() {
+            if bits.access(i) == 1 {
+                nodes += 1;
+            } else {
+                // the first zero terminates the children of the super root
+                zeros += 1;
+                assert!(zeros <= nodes + 1, "The degrees do not describe a tree");
+            }
+        }
+        Louds { bits }
+    }
+
+    /// Creates a tree from the degrees of its nodes in level order
+    pub fn from_degrees<I: IntoIterator<Item = usize>>(degrees: I) -> Self {
+        let mut bits = vec![true, false];
+        for degree in degrees {
+            bits.extend(std::iter::repeat_n(true, degree));
+            bits.push(false);
+        }
+        Self::new(bits.into_iter().collect())
+    }
+
+    /// Creates a tree from `adjacency` containing the children of every node, starting at `root`
+    /// Returns the tree and the original id of every node of the tree, as the nodes are renumbered in level order.
+    /// Nodes which are not reachable from `root` are not part of the tree.
+    /// Panics if a node is reached twice, i.e. the nodes reachable from `root` contain a cycle or a node with two parents.
+    pub fn from_adjacency(adjacency: &[Vec<usize>], root: usize) -> (Self, Vec<usize>) {
+        let mut order = Vec::with_capacity(adjacency.len());
+        let mut visited = vec![false; adjacency.len()];
+        visited[root] = true;
+        let mut queue = VecDeque::from([root]);
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            for &child in &adjacency[node] {
+                assert!(!visited[child], "Node {child} is reached twice, the adjacency does not describe a tree");
+                visited[child] = true;
+                queue.push_back(child);
+            }
+        }
+        (Self::from_degrees(order.iter().map(|&node| adjacency[node].len())), order)
+    }
+
+    /// Get the size of the tree including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<Louds>() + self.bits.get_size()
+    }
+
+    /// Get the unary encoded degrees as bit vector
+    #[inline]
+    pub fn as_bit_vector(&self) -> &BitVector {
+        &self.bits
+    }
+
+    /// Get the number of nodes
+    #[inline]
+    pub fn num_nodes(&self) -> usize {
+        self.bits.len() / 2
+    }
+
+    /// Get the position of the one representing `node`
+    #[inline]
+    pub fn node_position(&self, node: usize) -> usize {
+        self.bits.select1(node).expect("No ith node found")
+    }
+
+    /// Get the node represented by the one at `position`
+    #[inline]
+    pub fn node_id(&self, position: usize) -> usize {
+        assert_eq!(self.bits.access(position), 1, "No node at {position}");
+        self.bits.rank1(position)
+    }
+
+    /// Get the position of the first child of `node`, which may be the terminating zero
+    #[inline]
+    fn children_start(&self, node: usize) -> usize {
+        self.bits.select0(node).expect("No ith node found") + 1
+    }
+
+    /// Get the number of children of `node`
+    #[inline]
+    pub fn degree(&self, node: usize) -> usize {
+        let start = self.children_start(node);
+        self.bits.select0(node + 1).expect("No ith node found") - start
+    }
+
+    /// Whether `node` has no children
+    #[inline]
+    pub fn is_leaf(&self, node: usize) -> bool {
+        self.bits.access(self.children_start(node)) == 0
+    }
+
+    /// Get the `index`-th child of `node`, counting from zero
+    /// Returns `None` if `node` has at most `index` children.
+    #[inline]
+    pub fn child(&self, node: usize, index: usize) -> Option<usize> {
+        (index < self.degree(node)).then(|| self.bits.rank1(self.children_start(node) + index))
+    }
+
+    /// Get the first child of `node`
+    /// Returns `None` if `node` is a leaf.
+    #[inline]
+    pub fn first_child(&self, node: usize) -> Option<usize> {
+        let start = self.children_start(node);
+        (self.bits.access(start) == 1).then(|| self.bits.rank1(start))
+    }
+
+    /// Get the last child of `node`
+    /// Returns `None` if `node` is a leaf.
+    #[inline]
+    pub fn last_child(&self, node: usize) -> Option<usize> {
+        // the children of the next node start after the zero terminating the children of node
+        let end = self.children_start(node + 1) - 1;
+        (self.bits.access(end - 1) == 1).then(|| self.bits.rank1(end - 1))
+    }
+
+    /// Get the parent of `node`
+    /// Returns `None` if `node` is the root.
+    #[inline]
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        // the one of the node is in the children of the node whose zero precedes it
+        (node > 0).then(|| self.bits.rank0(self.node_position(node)) - 1)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::seq::SliceRandom;
+    use rand_chacha::ChaCha8Rng;
+    use crate::{BitVector, Louds};
+
+    /// A pointer based tree node
+    struct Node {
+        parent: Option<usize>,
+        children: Vec<usize>,
+    }
+
+    /// Creates a random tree whose node ids are shuffled, so they are not in level order
+    fn random_tree(nodes: usize, rng: &mut ChaCha8Rng) -> (Vec<Node>, usize) {
+        let mut ids: Vec<usize> = (0..nodes).collect();
+        ids.shuffle(rng);
+        let mut tree: Vec<Node> = (0..nodes).map(|_| Node { parent: None, children: Vec::new() }).collect();
+        for i in 1..nodes {
+            let parent = ids[rng.gen_range(0..i)];
+            tree[ids[i]].parent = Some(parent);
+            tree[parent].children.push(ids[i]);
+        }
+        (tree, ids[0])
+    }
+
+    fn test_against_reference(tree: &[Node], root: usize) {
+        let adjacency: Vec<Vec<usize>> = tree.iter().map(|node| node.children.clone()).collect();
+        let (louds, order) = Louds::from_adjacency(&adjacency, root);
+        assert_eq!(louds.num_nodes(), tree.len());
+        let mut ids = vec![0; tree.len()];
+        for (id, &node) in order.iter().enumerate() {
+            ids[node] = id;
+        }
+        for (id, &node) in order.iter().enumerate() {
+            let reference = &tree[node];
+            let children: Vec<usize> = reference.children.iter().map(|&child| ids[child]).collect();
+            assert_eq!(louds.node_id(louds.node_position(id)), id);
+            assert_eq!(louds.parent(id), reference.parent.map(|parent| ids[parent]));
+            assert_eq!(louds.degree(id), children.len());
+            assert_eq!(louds.is_leaf(id), children.is_empty());
+            assert_eq!(louds.first_child(id), children.first().copied());
+            assert_eq!(louds.last_child(id), children.last().copied());
+            for (i, &child) in children.iter().enumerate() {
+                assert_eq!(louds.child(id, i), Some(child));
+            }
+            assert_eq!(louds.child(id, children.len()), None);
+        }
+    }
+
+    #[test]
+    fn test_random_trees() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for nodes in [1, 2, 3, 10, 100, 5000] {
+            let (tree, root) = random_tree(nodes, &mut rng);
+            test_against_reference(&tree, root);
+        }
+    }
+
+    #[test]
+    fn test_bits() {
+        // a root with three children, the second one with two children
+        let louds = Louds::from_degrees([3, 0, 2, 0, 0, 0]);
+        assert_eq!(louds.as_bit_vector(), &BitVector::load_from_string("1011100110000"));
+        assert_eq!(louds.child(0, 1), Some(2));
+        assert_eq!(louds.first_child(2), Some(4));
+        assert_eq!(louds.last_child(2), Some(5));
+        assert_eq!(louds.parent(5), Some(2));
+        assert_eq!(louds.parent(0), None);
+        let louds = Louds::new(BitVector::load_from_string("1011100110000"));
+        assert_eq!(louds.degree(0), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "reached twice")]
+    fn test_adjacency_cycle() {
+        Louds::from_adjacency(&[vec![1], vec![2], vec![0]], 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "reached twice")]
+    fn test_adjacency_two_parents() {
+        Louds::from_adjacency(&[vec![1, 2], vec![3], vec![3], vec![]], 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid() {
+        Louds::new(BitVector::load_from_string("1001110"));
+    }
+}
@@ -41,12 +41,12 @@ fn main() {
     let path_in = &args[1];
     let path_out = &args[2];
     
-    let (mut bit_vector, commands) = parse_input(path_in);
+    let (bit_vector, commands) = parse_input(path_in);
     
     // start the timer
     let start_time = Instant::now();
     // generate the acceleration structures
-    bit_vector.init();
+    let bit_vector = bit_vector.build();
 
     let mut results = Vec::new();
     
@@ -54,8 +54,9 @@ fn main() {
     for command in commands {
         results.push(match command {
             Access {index} => bit_vector.access(index),
-            Rank {bit , index} => bit_vector.rank(bit, index),
-            Select {bit, index} => bit_vector.select(bit, index),
+            Rank {bit , index} => if bit { bit_vector.rank1(index) } else { bit_vector.rank0(index) },
+            // the input is one based but select0 and select1 are zero based
+            Select {bit, index} => if bit { bit_vector.select1(index - 1) } else { bit_vector.select0(index - 1) }.expect("No ith zero/one found"),
         });
     }
     let end_time = Instant::now();
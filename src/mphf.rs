@@ -0,0 +1,407 @@
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::mem::size_of;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use crate::BitVector;
+
+/// The default ratio between the size of a level and the number of keys hashed into it
+const DEFAULT_GAMMA: f64 = 1.0;
+
+/// The default seed of the hash functions
+const DEFAULT_SEED: u64 = 1234567;
+
+/// The maximum number of levels, distinct keys practically never need as many
+const MAX_LEVELS: usize = 64;
+
+/// The number of bits between two samples of the number of ones
+/// The blocks of `RankAccelerator` need more bits than the levels themselves, so sparse samples keep the function at about 3 bits per key.
+const RANK_SAMPLE_RATE: usize = 512;
+
+/// A hasher whose result only depends on its seed and the written bytes
+/// Integers are written in little endian, so serialized levels stay valid on every platform.
+struct SeededHasher {
+    state: u64,
+}
+
+impl SeededHasher {
+    /// Creates a hasher for the level with `seed`
+    fn new(seed: u64) -> Self {
+        SeededHasher { state: seed ^ 0xcbf2_9ce4_8422_2325 }
+    }
+}
+
+impl Hasher for SeededHasher {
+    /// Mixes the `bytes` into the state using FNV-1a
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state = (self.state ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    /// Finalizes the state like SplitMix64, so all bits depend on all bytes
+    fn finish(&self) -> u64 {
+        let mut x = self.state;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^ (x >> 31)
+    }
+}
+
+/// A level of the function
+#[derive(Clone)]
+struct Level {
+    /// The seed of the hash function of the level
+    seed: u64,
+    /// The positions to which exactly one of the keys reaching the level is hashed
+    bits: BitVector,
+    /// The number of ones before every `RANK_SAMPLE_RATE`-th bit and before the end, including all previous levels
+    samples: Vec<usize>,
+}
+
+impl Level {
+    /// Creates a level with the hash function of `seed` from its `bits`, after `ones` ones in the previous levels
+    fn new(seed: u64, bits: BitVector, ones: usize) -> Self {
+        let mut samples = Vec::with_capacity(bits.len().div_ceil(RANK_SAMPLE_RATE) + 1);
+        let mut count = ones;
+        for start in (0..bits.len()).step_by(RANK_SAMPLE_RATE) {
+            samples.push(count);
+            count += bits.count_ones(start..bits.len().min(start + RANK_SAMPLE_RATE));
+        }
+        // the end may be the start of a new sample
+        samples.push(count);
+        Level { seed, bits, samples }
+    }
+
+    /// Get the position of `key` in the level
+    #[inline]
+    fn position<K: Hash + ?Sized>(&self, key: &K) -> usize {
+        position(key, self.seed, self.bits.len())
+    }
+
+    /// Get the number of ones before `index` in this and all previous levels
+    #[inline]
+    fn rank1(&self, index: usize) -> usize {
+        let sample = index / RANK_SAMPLE_RATE;
+        self.samples[sample] + self.bits.count_ones(sample * RANK_SAMPLE_RATE..index)
+    }
+}
+
+/// Hash `key` with the hash function of `seed` to a position in `0..len`
+#[inline]
+fn position<K: Hash + ?Sized>(key: &K, seed: u64, len: usize) -> usize {
+    let mut hasher = SeededHasher::new(seed);
+    key.hash(&mut hasher);
+    // multiply and shift instead of a modulo
+    ((hasher.finish() as u128 * len as u128) >> 64) as usize
+}
+
+/// A minimal perfect hash function mapping `n` distinct keys to `0..n` without storing the keys (BBHash)
+/// The keys are hashed into a first level of `gamma * n` bits.
+/// The positions hit by exactly one key are set, the keys colliding with others are hashed into the next level, which is smaller.
+/// A key is mapped to the number of ones before its position in all levels, which is about 3 bits per key for `gamma = 1`.
+/// Larger `gamma` causes less collisions, so construction and queries are faster at the cost of more space.
+/// The hash functions of the levels are seeded deterministically by a `ChaCha8Rng`, so the same keys and seed give the same function.
+#[derive(Clone)]
+pub struct Mphf {
+    /// The number of keys
+    len: usize,
+    /// The ratio between the size of a level and the number of keys hashed into it
+    gamma: f64,
+    /// The seed of the random number generator creating the seeds of the levels
+    seed: u64,
+    /// The levels in the order the keys are hashed into them
+    levels: Vec<Level>,
+}
+
+impl Mphf {
+    /// Creates a minimal perfect hash function for the distinct `keys` with the default gamma and seed
+    pub fn new<K: Hash>(keys: &[K]) -> Self {
+        Self::with_parameters(keys, DEFAULT_GAMMA, DEFAULT_SEED)
+    }
+
+    /// Creates a minimal perfect hash function for the distinct `keys` with levels of `gamma` bits per key and the hash functions of `seed`
+    /// Panics if `gamma` is less than one or the keys are not distinct.
+    pub fn with_parameters<K: Hash>(keys: &[K], gamma: f64, seed: u64) -> Self {
+        assert!(gamma >= 1.0, "gamma {gamma} is less than one");
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut levels = Vec::new();
+        let mut remaining: Vec<&K> = keys.iter().collect();
+        let mut ones = 0;
+        while !remaining.is_empty() {
+            assert!(levels.len() < MAX_LEVELS, "The level limit of {MAX_LEVELS} was exceeded, the keys may contain duplicates");
+            let level_seed = rng.gen();
+            let len = ((gamma * remaining.len() as f64).ceil() as usize).max(1);
+            // a position is set in `hit` by the first key and in `collision` by every further key
+            let mut hit = BitVector::new();
+            hit.resize(len);
+            let mut collision = hit.clone();
+            for key in &remaining {
+                let position = position(key, level_seed, len);
+                if hit.access(position) == 1 {
+                    collision.set(position, true);
+                } else {
+                    hit.set(position, true);
+                }
+            }
+            remaining.retain(|key| collision.access(position(key, level_seed, len)) == 1);
+            for position in 0..len {
+                if collision.access(position) == 1 {
+                    hit.set(position, false);
+                }
+            }
+            let level = Level::new(level_seed, hit, ones);
+            ones = level.rank1(len);
+            levels.push(level);
+        }
+        Mphf {
+            len: keys.len(),
+            gamma,
+            seed,
+            levels,
+        }
+    }
+
+    /// Get the size of the function including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<Mphf>()
+        + self.levels.capacity() * size_of::<Level>()
+        + self.levels.iter().map(|level| level.bits.get_size() + level.samples.capacity() * size_of::<usize>()).sum::<usize>()
+    }
+
+    /// Get the number of keys
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no keys
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the ratio between the size of a level and the number of keys hashed into it
+    #[inline]
+    pub fn gamma(&self) -> f64 {
+        self.gamma
+    }
+
+    /// Get the seed of the hash functions
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Get the number of levels
+    #[inline]
+    pub fn num_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Get the index of `key` in `0..len()`
+    /// Every key of the construction gets a different index.
+    /// Other keys get an arbitrary index or `None` if they collide on every level.
+    pub fn get<K: Hash + ?Sized>(&self, key: &K) -> Option<usize> {
+        self.levels.iter().find_map(|level| {
+            let position = level.position(key);
+            (level.bits.access(position) == 1).then(|| level.rank1(position))
+        })
+    }
+
+    /// Write the function to `writer`
+    /// All numbers are written as little endian `u64`, the bits of every level in words of 64 bits.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for value in [self.len as u64, self.gamma.to_bits(), self.seed, self.levels.len() as u64] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for level in &self.levels {
+            let bits = &level.bits;
+            writer.write_all(&(bits.len() as u64).to_le_bytes())?;
+            for start in (0..bits.len()).step_by(u64::BITS as usize) {
+                let word = bits.get_bits(start, (bits.len() - start).min(u64::BITS as usize));
+                writer.write_all(&word.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a function written by `write_to` from `reader`
+    /// The seeds of the levels are created again from the seed, the rank samples are recomputed.
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut read_u64 = || -> io::Result<u64> {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(u64::from_le_bytes(bytes))
+        };
+        let len = read_u64()? as usize;
+        let gamma = f64::from_bits(read_u64()?);
+        let seed = read_u64()?;
+        let num_levels = read_u64()? as usize;
+        if num_levels > MAX_LEVELS {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{num_levels} levels are more than {MAX_LEVELS}")));
+        }
+        if !(gamma >= 1.0 && gamma.is_finite()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("gamma {gamma} is not a finite number of at least one")));
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut levels = Vec::with_capacity(num_levels);
+        let mut ones = 0;
+        // the levels get shorter with the remaining keys, so a corrupt length can not make us allocate more than the first level
+        let mut max_len = ((gamma * len as f64).ceil() as usize).max(1);
+        for _ in 0..num_levels {
+            let level_len = read_u64()? as usize;
+            if level_len > max_len {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("a level of length {level_len} is longer than {max_len}")));
+            }
+            max_len = level_len;
+            let mut bits = BitVector::new();
+            bits.resize(level_len);
+            for start in (0..level_len).step_by(u64::BITS as usize) {
+                let width = (level_len - start).min(u64::BITS as usize);
+                let word = read_u64()?;
+                if width < u64::BITS as usize && word >> width != 0 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "bits after the end of a level are set"));
+                }
+                bits.set_bits(start, width, word);
+            }
+            let level = Level::new(rng.gen(), bits, ones);
+            ones = level.rank1(level_len);
+            levels.push(level);
+        }
+        if ones != len {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("the levels contain {ones} keys instead of {len}")));
+        }
+        Ok(Mphf {
+            len,
+            gamma,
+            seed,
+            levels,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashSet;
+    use std::io;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::Mphf;
+
+    /// Check that `mphf` maps the `keys` to a permutation of `0..keys.len()`
+    fn assert_minimal_perfect(mphf: &Mphf, keys: &[u64]) {
+        assert_eq!(mphf.len(), keys.len());
+        let mut seen = vec![false; keys.len()];
+        for key in keys {
+            let index = mphf.get(key).expect("Key not found");
+            assert!(!seen[index], "index {index} is used twice");
+            seen[index] = true;
+        }
+    }
+
+    fn random_keys(len: usize) -> Vec<u64> {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let keys: HashSet<u64> = (0..len).map(|_| rng.gen()).collect();
+        let mut keys: Vec<u64> = keys.into_iter().collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn test_minimal_perfect() {
+        let keys = random_keys(100000);
+        let mphf = Mphf::new(&keys);
+        assert_minimal_perfect(&mphf, &keys);
+        // about 3 bits per key for gamma = 1
+        let bits_per_key = 8.0 * mphf.get_size() as f64 / keys.len() as f64;
+        assert!(bits_per_key < 4.0, "{bits_per_key} bits per key");
+        // a level whose length is a multiple of the sample rate
+        let keys = random_keys(512);
+        assert_minimal_perfect(&Mphf::new(&keys), &keys);
+        for gamma in [1.5, 2.0, 5.0] {
+            let mphf = Mphf::with_parameters(&keys, gamma, 42);
+            assert_minimal_perfect(&mphf, &keys);
+            assert!(mphf.num_levels() > 1);
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let keys = random_keys(10000);
+        let first = Mphf::with_parameters(&keys, 2.0, 7);
+        let second = Mphf::with_parameters(&keys, 2.0, 7);
+        let other = Mphf::with_parameters(&keys, 2.0, 8);
+        assert!(keys.iter().all(|key| first.get(key) == second.get(key)));
+        assert!(keys.iter().any(|key| first.get(key) != other.get(key)));
+    }
+
+    #[test]
+    fn test_serialization() {
+        let keys = random_keys(10000);
+        let mphf = Mphf::with_parameters(&keys, 1.5, 99);
+        let mut bytes = Vec::new();
+        mphf.write_to(&mut bytes).unwrap();
+        let read = Mphf::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.len(), mphf.len());
+        assert_eq!(read.gamma(), 1.5);
+        assert_eq!(read.seed(), 99);
+        assert_eq!(read.num_levels(), mphf.num_levels());
+        assert!(keys.iter().all(|key| read.get(key) == mphf.get(key)));
+        assert!(Mphf::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_header() {
+        let keys = random_keys(1000);
+        let mut bytes = Vec::new();
+        Mphf::new(&keys).write_to(&mut bytes).unwrap();
+        // the header is the length, gamma, the seed and the number of levels followed by the length of the first level
+        for gamma in [0.5, f64::NAN, f64::INFINITY] {
+            let mut corrupt = bytes.clone();
+            corrupt[8..16].copy_from_slice(&gamma.to_bits().to_le_bytes());
+            assert_eq!(Mphf::read_from(&mut corrupt.as_slice()).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        }
+        let mut corrupt = bytes.clone();
+        corrupt[32..40].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(Mphf::read_from(&mut corrupt.as_slice()).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_string_keys() {
+        let keys: Vec<String> = (0..1000).map(|i| format!("key{i}")).collect();
+        let mphf = Mphf::new(&keys);
+        let indices: HashSet<usize> = keys.iter().map(|key| mphf.get(key.as_str()).unwrap()).collect();
+        assert_eq!(indices, (0..keys.len()).collect());
+        let empty = Mphf::new::<u8>(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.get(&1u8), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "the keys may contain duplicates")]
+    fn test_duplicate_keys() {
+        Mphf::new(&[1, 2, 3, 2]);
+    }
+}
@@ -0,0 +1,193 @@
+use std::mem::size_of;
+use crate::{BitVector, RankSelect, SparseBitVector};
+
+/// Prefix sums of a static sequence of non-negative integers
+/// Every value `v` is encoded in unary as `v` zeros followed by a one.
+/// Then, the sum of the first `i + 1` values is the number of zeros before the `i`-th one,
+/// and the value containing the `s`-th unit of the sum is the number of ones before the `s`-th zero.
+/// The bits are stored in a plain `BitVector` by default, which suits small values.
+/// For large values `EliasFanoPrefixSums` stores the positions of the ones in a `SparseBitVector` instead.
+pub struct PrefixSums<B = BitVector> {
+    /// The number of values
+    len: usize,
+    /// The sum of all values
+    total: u64,
+    /// The unary encoded values
+    bits: B,
+}
+
+/// Prefix sums storing the unary encoded values in a `SparseBitVector`, so their size does not grow with the values
+pub type EliasFanoPrefixSums = PrefixSums<SparseBitVector>;
+
+/// Get the positions of the ones of the unary encoded `values` and the length of the encoding
+fn unary_positions(values: &[u64]) -> (impl Iterator<Item = usize> + '_, usize) {
+    let total: u64 = values.iter().sum();
+    let mut sum = 0;
+    let positions = values.iter().enumerate().map(move |(i, &value)| {
+        sum += value as usize;
+        sum + i
+    });
+    (positions, total as usize + values.len())
+}
+
+impl From<Vec<u64>> for PrefixSums<BitVector> {
+    /// Creates the prefix sums of `values`
+    fn from(values: Vec<u64>) -> Self {
+        Self::new(&values)
+    }
+}
+
+impl From<Vec<u64>> for PrefixSums<SparseBitVector> {
+    /// Creates the prefix sums of `values` using Elias-Fano encoding
+    fn from(values: Vec<u64>) -> Self {
+        Self::new_elias_fano(&values)
+    }
+}
+
+impl PrefixSums<BitVector> {
+    /// Creates the prefix sums of `values`
+    pub fn new(values: &[u64]) -> Self {
+        let mut bits: BitVector = values.iter().flat_map(|&value| {
+            std::iter::repeat_n(false, value as usize).chain([true])
+        }).collect();
+        bits.init();
+        PrefixSums {
+            len: values.len(),
+            total: values.iter().sum(),
+            bits,
+        }
+    }
+
+    /// Get the size of the prefix sums including space on the heap
+    pub fn get_size(&self) -> usize {
+        // `BitVector::get_size` only counts the heap, so its header is part of `PrefixSums`
+        size_of::<PrefixSums<BitVector>>() + self.bits.get_size()
+    }
+}
+
+impl PrefixSums<SparseBitVector> {
+    /// Creates the prefix sums of `values` using Elias-Fano encoding
+    pub fn new_elias_fano(values: &[u64]) -> Self {
+        let (positions, len) = unary_positions(values);
+        PrefixSums {
+            len: values.len(),
+            total: values.iter().sum(),
+            bits: SparseBitVector::from_positions(positions, len),
+        }
+    }
+
+    /// Get the size of the prefix sums including space on the heap
+    pub fn get_size(&self) -> usize {
+        // `SparseBitVector::get_size` already counts its header
+        size_of::<PrefixSums<SparseBitVector>>() - size_of::<SparseBitVector>() + self.bits.get_size()
+    }
+}
+
+impl<B: RankSelect> PrefixSums<B> {
+    /// Get the number of values
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no values
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the sum of all values
+    #[inline]
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Get the sum of the values before `index`, i.e. in `0..index`
+    /// `index` may be equal to the number of values.
+    #[inline]
+    pub fn prefix_sum(&self, index: usize) -> u64 {
+        assert!(index <= self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        if index == 0 {
+            return 0;
+        }
+        // the number of zeros before the one ending the value at `index - 1`
+        (self.bits.select1(index - 1).expect("No ith value found") - (index - 1)) as u64
+    }
+
+    /// Get the value at `index`
+    #[inline]
+    pub fn get(&self, index: usize) -> u64 {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        self.prefix_sum(index + 1) - self.prefix_sum(index)
+    }
+
+    /// Get the index `i` of the value with `prefix_sum(i) <= sum < prefix_sum(i + 1)`
+    /// Returns `None` if `sum` is at least the total.
+    #[inline]
+    pub fn find(&self, sum: u64) -> Option<usize> {
+        if sum >= self.total {
+            return None;
+        }
+        // the values ending before the `sum`-th zero
+        Some(self.bits.rank1(self.bits.select0(sum as usize).expect("No ith zero found")))
+    }
+
+    /// Iterate over the values
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).map(|i| self.get(i))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::{EliasFanoPrefixSums, PrefixSums, RankSelect};
+
+    fn test_against_naive<B: RankSelect>(prefix_sums: &PrefixSums<B>, values: &[u64], rng: &mut ChaCha8Rng) {
+        let sums: Vec<u64> = std::iter::once(0).chain(values.iter().scan(0, |sum, &value| {
+            *sum += value;
+            Some(*sum)
+        })).collect();
+        assert_eq!(prefix_sums.len(), values.len());
+        assert_eq!(prefix_sums.total(), sums[values.len()]);
+        assert_eq!(prefix_sums.iter().collect::<Vec<u64>>(), values);
+        for (i, &sum) in sums.iter().enumerate() {
+            assert_eq!(prefix_sums.prefix_sum(i), sum);
+        }
+        for _ in 0..1000 {
+            let sum = rng.gen_range(0..=prefix_sums.total() + 1);
+            let expected = (sum < prefix_sums.total()).then(|| sums.partition_point(|&s| s <= sum) - 1);
+            assert_eq!(prefix_sums.find(sum), expected);
+        }
+    }
+
+    #[test]
+    fn test_small_values() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let values: Vec<u64> = (0..3000).map(|_| rng.gen_range(0..10)).collect();
+        test_against_naive(&PrefixSums::new(&values), &values, &mut rng);
+        test_against_naive(&EliasFanoPrefixSums::new_elias_fano(&values), &values, &mut rng);
+    }
+
+    #[test]
+    fn test_large_values() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let values: Vec<u64> = (0..3000).map(|_| rng.gen_range(0..1000000000)).collect();
+        let prefix_sums = EliasFanoPrefixSums::from(values.clone());
+        test_against_naive(&prefix_sums, &values, &mut rng);
+        // the plain variant would need one bit per unit of the total
+        assert!(prefix_sums.get_size() * 1000 < prefix_sums.total() as usize / 8);
+    }
+
+    #[test]
+    fn test_small() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for values in [vec![], vec![0], vec![5], vec![0, 0, 3, 0, 1], vec![2, 0, 0]] {
+            let prefix_sums: PrefixSums = values.clone().into();
+            test_against_naive(&prefix_sums, &values, &mut rng);
+            test_against_naive(&EliasFanoPrefixSums::from(values.clone()), &values, &mut rng);
+        }
+    }
+}
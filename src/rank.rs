@@ -4,6 +4,7 @@ use std::mem::size_of;
 use crate::BitVector;
 
 /// An accelerator used to for rank operations
+#[derive(Clone)]
 pub struct RankAccelerator {
     /// Holds blocks.
     /// Each entry contains the number of ones from the beginning of the super block to the end of the block
@@ -38,17 +39,31 @@ impl RankAccelerator {
         + self.super_blocks.capacity() * size_of::<usize>()
     }
 
+    /// Estimate the size of the rank accelerator of a bit vector with `len` bits without building it
+    pub fn estimate_size(len: usize) -> usize {
+        let block_size = Self::block_size(len);
+        size_of::<RankAccelerator>()
+        + len.div_ceil(block_size) * size_of::<u16>()
+        + len.div_ceil(block_size.pow(2)) * size_of::<usize>()
+    }
+
+    /// Get the block size for a bit vector with `len` bits
+    fn block_size(len: usize) -> usize {
+        // calculate block size as suggested in the lecture
+        // an empty vector has no logarithm, but it still gets blocks of size one, so queries do not have to handle it separately
+        cmp::max((len.checked_ilog2().unwrap_or(0) as f64 / 2f64) as usize, 1)
+    }
+
     /// Initialize the rank accelerator using the `bit_vector`
     pub fn init(&mut self, bit_vector: &BitVector) {
-        // calculate block size and super blocks size as suggested in the lecture
-        self.block_size = cmp::max((bit_vector.len().ilog2() as f64 / 2f64) as usize, 1);
+        self.block_size = Self::block_size(bit_vector.len());
         self.super_block_size = self.block_size.pow(2);
 
         // generate super blocks
         // the number of super blocks is already known therefore this will save space and time because the vector does not have to grow
         self.super_blocks.reserve_exact(bit_vector.len().div_ceil(self.super_block_size));
         // create the first super block
-        let num_ones_until_enf_of_block_0 = bit_vector.count_ones(0..self.super_block_size);
+        let num_ones_until_enf_of_block_0 = bit_vector.count_ones(0..min(self.super_block_size, bit_vector.len()));
         self.super_blocks.push(num_ones_until_enf_of_block_0);
 
         // create subsequent super blocks using the previous block and `count_ones` to count the ones in the current block
@@ -84,6 +99,32 @@ impl RankAccelerator {
         }
     }
 
+    /// Update the rank accelerator after the last bit of the `bit_vector` was appended
+    /// The block sizes depend on the logarithm of the length, so the accelerator is rebuilt whenever the length reaches a power of two.
+    /// Otherwise only the last block and super block are updated, which makes appending amortised constant time.
+    pub fn push(&mut self, bit_vector: &BitVector) {
+        if bit_vector.len().is_power_of_two() {
+            *self = Self::new();
+            self.init(bit_vector);
+            return;
+        }
+        let index = bit_vector.len() - 1;
+        let bit = bit_vector.access(index);
+        // a new super block starts with the ones of all previous super blocks
+        if index.is_multiple_of(self.super_block_size) {
+            self.super_blocks.push(self.super_blocks.last().unwrap_or(&0) + bit);
+        } else {
+            *self.super_blocks.last_mut().unwrap() += bit;
+        }
+        // a new block starts with the ones of the previous blocks in its super block
+        if index.is_multiple_of(self.block_size) {
+            let previous = if index.is_multiple_of(self.super_block_size) { 0 } else { *self.blocks.last().unwrap() };
+            self.blocks.push(previous + bit as u16);
+        } else {
+            *self.blocks.last_mut().unwrap() += bit as u16;
+        }
+    }
+
     /// Count the ones until `index` in the `block`
     #[inline]
     fn get_ones(block: u32, index: usize) -> usize {
@@ -98,8 +139,10 @@ impl RankAccelerator {
         block.count_ones() as usize
     }
 
-    /// Get the number of zero/one's before `index` from the `bit_vector`
-    pub fn rank(&self, bit: bool, index: usize, bit_vector: &BitVector) -> usize {
+    /// Get the number of ones before `index` from the `bit_vector`
+    /// `index` may be equal to the length of the `bit_vector` to count all ones.
+    #[inline]
+    pub fn rank1(&self, index: usize, bit_vector: &BitVector) -> usize {
         // calculate super block index
         let super_block = index / self.super_block_size;
         // calculate block index
@@ -110,14 +153,19 @@ impl RankAccelerator {
         // Count the ones until the start of the super block
         let result1 = self.super_blocks.get(super_block.wrapping_sub(1)).unwrap_or(&0);
         // Count the ones until the start of the block
-        let result2 = if block % (self.super_block_size / self.block_size) == 0 { 0 } else { self.blocks[block - 1] as usize };
+        let result2 = if block.is_multiple_of(self.super_block_size / self.block_size) { 0 } else { self.blocks[block - 1] as usize };
         // Count the ones inside the block until index
-        let result3 = Self::get_ones(bit_vector.access_block(block_start) as u32, index % self.block_size);
+        // If index is the start of a block there is nothing to count and the block may even start behind the end of the bit vector.
+        let result3 = if index.is_multiple_of(self.block_size) { 0 } else { Self::get_ones(bit_vector.access_block(block_start) as u32, index % self.block_size) };
 
-        let result = result1 + result2 + result3;
+        result1 + result2 + result3
+    }
 
-        // in the range from zero to index we found result many ones => index - result is the number of zeroes in this range
-        if bit { result } else { index - result }
+    /// Get the number of zeros before `index` from the `bit_vector`
+    #[inline]
+    pub fn rank0(&self, index: usize, bit_vector: &BitVector) -> usize {
+        // in the range from zero to index we found rank1 many ones => index - rank1 is the number of zeroes in this range
+        index - self.rank1(index, bit_vector)
     }
 }
 
@@ -131,7 +179,7 @@ pub mod test {
     fn test_init(data: &str) {
         let mut bit_vector = BitVector::load_from_string(data);
         bit_vector.init_rank_structures();
-        let rank_accelerator = bit_vector.rank_accelerator.as_ref().unwrap();
+        let rank_accelerator = bit_vector.rank_accelerator.get().unwrap();
         for (i, super_block) in rank_accelerator.super_blocks.iter().enumerate() {
             let mut sum = 0;
             for current_bit in 0..((i+1) * rank_accelerator.super_block_size) {
@@ -0,0 +1,153 @@
+use std::mem::size_of;
+use std::ops::Range;
+use crate::{BalancedParens, BitVector};
+
+/// Range minimum queries over a static array in 2n + o(n) bits without keeping the array
+/// The parent of every value is the closest previous value which is not larger, values without one are children of a virtual root.
+/// This tree is stored as `BalancedParens`, whose preorder is the order of the array after the virtual root.
+/// Every subtree is a range of the array whose first value is its minimum,
+/// and the children of a node are strictly decreasing from left to right.
+/// So the minimum of a range is either its first value or the child of the lowest common ancestor on the path to its last value.
+pub struct Rmq {
+    /// The tree of the array including the virtual root
+    parens: BalancedParens,
+}
+
+impl Rmq {
+    /// Creates range minimum queries over `values`
+    pub fn new<T: Ord>(values: &[T]) -> Self {
+        let mut bits = BitVector::new();
+        // the virtual root
+        bits.push(true);
+        let mut stack: Vec<&T> = Vec::new();
+        for value in values {
+            // close the subtrees of all larger values, the remaining top is the parent
+            while stack.last().is_some_and(|&top| top > value) {
+                stack.pop();
+                bits.push(false);
+            }
+            stack.push(value);
+            bits.push(true);
+        }
+        bits.extend(std::iter::repeat_n(false, stack.len() + 1));
+        Rmq {
+            parens: BalancedParens::new(bits),
+        }
+    }
+
+    /// Get the size of the range minimum queries including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<Rmq>() - size_of::<BalancedParens>() + self.parens.get_size()
+    }
+
+    /// Get the number of values
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.parens.num_nodes() - 1
+    }
+
+    /// Whether there are no values
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the index of the minimum in `range`, the leftmost one if there are several
+    /// Panics if `range` is empty or out of bounds.
+    pub fn argmin(&self, range: Range<usize>) -> usize {
+        assert!(range.start < range.end && range.end <= self.len(), "range {range:?} is empty or out of bounds for length {}", self.len());
+        let (first, last) = (range.start, range.end - 1);
+        // the virtual root is the first node
+        let x = self.parens.node(first + 1).expect("No ith node found");
+        let y = self.parens.node(last + 1).expect("No ith node found");
+        if self.parens.is_ancestor(x, y) {
+            return first;
+        }
+        // after leaving the subtree of the lowest common ancestor's child containing `x`,
+        // the excess is one more than the depth of the lowest common ancestor at the start of every following child
+        let min = self.parens.min_excess(x + 1, y);
+        let child = self.parens.bwd_search(y, min).expect("No child of the lowest common ancestor found");
+        self.parens.node_id(child) - 1
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::Rmq;
+
+    /// A naive sparse table storing the leftmost minimum of every range whose length is a power of two
+    struct SparseTable<'a> {
+        values: &'a [u32],
+        table: Vec<Vec<usize>>,
+    }
+
+    impl<'a> SparseTable<'a> {
+        fn new(values: &'a [u32]) -> Self {
+            let mut table = vec![(0..values.len()).collect::<Vec<usize>>()];
+            let mut width = 1;
+            while 2 * width <= values.len() {
+                let previous = table.last().unwrap();
+                let level = (0..=values.len() - 2 * width).map(|i| min_index(values, previous[i], previous[i + width])).collect();
+                table.push(level);
+                width *= 2;
+            }
+            SparseTable { values, table }
+        }
+
+        fn argmin(&self, from: usize, to: usize) -> usize {
+            let level = (to - from).ilog2() as usize;
+            min_index(self.values, self.table[level][from], self.table[level][to - (1 << level)])
+        }
+    }
+
+    /// The index of the smaller value, the left one on ties
+    fn min_index(values: &[u32], left: usize, right: usize) -> usize {
+        if values[right] < values[left] { right } else { left }
+    }
+
+    fn test_against_sparse_table(values: &[u32], rng: &mut ChaCha8Rng) {
+        let rmq = Rmq::new(values);
+        let table = SparseTable::new(values);
+        assert_eq!(rmq.len(), values.len());
+        for _ in 0..2000 {
+            let from = rng.gen_range(0..values.len());
+            let to = rng.gen_range(from + 1..=values.len());
+            assert_eq!(rmq.argmin(from..to), table.argmin(from, to), "range {from}..{to}");
+        }
+        for i in 0..values.len() {
+            assert_eq!(rmq.argmin(i..i + 1), i);
+        }
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let values: Vec<u32> = (0..5000).map(|_| rng.gen()).collect();
+        test_against_sparse_table(&values, &mut rng);
+        // many equal values test the leftmost minimum
+        let values: Vec<u32> = (0..5000).map(|_| rng.gen_range(0..5)).collect();
+        test_against_sparse_table(&values, &mut rng);
+    }
+
+    #[test]
+    fn test_monotone() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        // a path as tree, the excess gets as large as the array
+        test_against_sparse_table(&(0..3000).collect::<Vec<u32>>(), &mut rng);
+        test_against_sparse_table(&(0..3000).rev().collect::<Vec<u32>>(), &mut rng);
+        test_against_sparse_table(&[7; 100], &mut rng);
+    }
+
+    #[test]
+    fn test_small() {
+        let rmq = Rmq::new(&[3, 1, 4, 1, 5, 9, 2, 6]);
+        assert_eq!(rmq.argmin(0..8), 1);
+        assert_eq!(rmq.argmin(2..8), 3);
+        assert_eq!(rmq.argmin(4..8), 6);
+        assert_eq!(rmq.argmin(4..6), 4);
+        assert!(Rmq::new::<u8>(&[]).is_empty());
+    }
+}
@@ -0,0 +1,389 @@
+use std::mem::size_of;
+use crate::BitVector;
+
+/// The number of bits per block
+const BLOCK_SIZE: usize = 15;
+/// The number of bits used to store the class of a block
+const CLASS_WIDTH: usize = 4;
+/// The number of blocks between two samples
+const SAMPLE_RATE: usize = 32;
+
+/// `BINOMIAL[n][k]` is n choose k
+const BINOMIAL: [[usize; BLOCK_SIZE + 1]; BLOCK_SIZE + 1] = binomial_table();
+
+/// `OFFSET_WIDTH[c]` is the number of bits required to store the offset of a block of class `c`
+const OFFSET_WIDTH: [usize; BLOCK_SIZE + 1] = offset_width_table();
+
+/// Calculates the binomial coefficients up to `BLOCK_SIZE` using Pascal's triangle
+const fn binomial_table() -> [[usize; BLOCK_SIZE + 1]; BLOCK_SIZE + 1] {
+    let mut table = [[0; BLOCK_SIZE + 1]; BLOCK_SIZE + 1];
+    let mut n = 0;
+    while n <= BLOCK_SIZE {
+        table[n][0] = 1;
+        let mut k = 1;
+        while k <= n {
+            table[n][k] = table[n - 1][k - 1] + table[n - 1][k];
+            k += 1;
+        }
+        n += 1;
+    }
+    table
+}
+
+/// Calculates `ceil(log(BLOCK_SIZE choose c))` for every class `c`
+const fn offset_width_table() -> [usize; BLOCK_SIZE + 1] {
+    let mut table = [0; BLOCK_SIZE + 1];
+    let mut class = 0;
+    while class <= BLOCK_SIZE {
+        let blocks = BINOMIAL[BLOCK_SIZE][class];
+        table[class] = if blocks <= 1 { 0 } else { (blocks - 1).ilog2() as usize + 1 };
+        class += 1;
+    }
+    table
+}
+
+/// A compressed bit vector using the encoding of Raman, Raman and Rao
+/// The bits are split into blocks of `BLOCK_SIZE` bits.
+/// Every block is stored as its class, the number of ones inside the block, and its offset,
+/// the index of the block among all blocks of the same class.
+/// Blocks of class zero or `BLOCK_SIZE` do not need an offset, so long runs of equal bits compress well.
+/// Every `SAMPLE_RATE` blocks the rank and the position of the offset are sampled.
+pub struct RrrBitVector {
+    /// The number of bits in the bit vector
+    len: usize,
+    /// The number of ones in the bit vector
+    ones: usize,
+    /// The packed classes of the blocks, `CLASS_WIDTH` bits each
+    classes: BitVector,
+    /// The packed offsets of the blocks, their width depends on the class
+    offsets: BitVector,
+    /// The number of ones before every `SAMPLE_RATE`-th block
+    rank_samples: Vec<usize>,
+    /// The position inside `offsets` of every `SAMPLE_RATE`-th block
+    offset_samples: Vec<usize>,
+}
+
+impl Default for RrrBitVector {
+    /// Creates an empty RRR bit vector
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&BitVector> for RrrBitVector {
+    /// Compresses `bit_vector`
+    fn from(bit_vector: &BitVector) -> Self {
+        let num_blocks = bit_vector.len().div_ceil(BLOCK_SIZE);
+        let mut classes = Vec::with_capacity(num_blocks * CLASS_WIDTH);
+        let mut offsets = Vec::new();
+        let mut rank_samples = Vec::with_capacity(num_blocks.div_ceil(SAMPLE_RATE));
+        let mut offset_samples = Vec::with_capacity(num_blocks.div_ceil(SAMPLE_RATE));
+        let mut ones = 0;
+        for block_index in 0..num_blocks {
+            if block_index.is_multiple_of(SAMPLE_RATE) {
+                rank_samples.push(ones);
+                offset_samples.push(offsets.len());
+            }
+            let start = block_index * BLOCK_SIZE;
+            let width = BLOCK_SIZE.min(bit_vector.len() - start);
+            let block = bit_vector.get_bits(start, width) as u16;
+            let (class, offset) = Self::encode(block);
+            classes.extend((0..CLASS_WIDTH).map(|j| (class >> j) & 1 == 1));
+            offsets.extend((0..OFFSET_WIDTH[class]).map(|j| (offset >> j) & 1 == 1));
+            ones += class;
+        }
+        RrrBitVector {
+            len: bit_vector.len(),
+            ones,
+            classes: classes.into_iter().collect(),
+            offsets: offsets.into_iter().collect(),
+            rank_samples,
+            offset_samples,
+        }
+    }
+}
+
+impl RrrBitVector {
+    /// Creates an empty RRR bit vector
+    pub fn new() -> Self {
+        Self::from(&BitVector::new())
+    }
+
+    /// Get the class and offset of `block`
+    /// The offset is the position of `block` in the lexicographic order of all blocks of its class.
+    fn encode(block: u16) -> (usize, usize) {
+        let class = block.count_ones() as usize;
+        let mut offset = 0;
+        let mut remaining = class;
+        // every one at position p with `remaining` ones up to and including it skips all blocks which have these ones below p
+        for position in (0..BLOCK_SIZE).rev() {
+            if (block >> position) & 1 == 1 {
+                offset += BINOMIAL[position][remaining];
+                remaining -= 1;
+            }
+        }
+        (class, offset)
+    }
+
+    /// Get the block of the `class` at `offset`
+    fn decode(class: usize, offset: usize) -> u16 {
+        let mut block = 0;
+        let mut offset = offset;
+        let mut remaining = class;
+        for position in (0..BLOCK_SIZE).rev() {
+            if remaining > 0 && offset >= BINOMIAL[position][remaining] {
+                block |= 1 << position;
+                offset -= BINOMIAL[position][remaining];
+                remaining -= 1;
+            }
+        }
+        block
+    }
+
+    /// Get the class of the `block_index`-th block
+    #[inline]
+    fn class(&self, block_index: usize) -> usize {
+        self.classes.get_bits(block_index * CLASS_WIDTH, CLASS_WIDTH) as usize
+    }
+
+    /// Get the `block_index`-th block using the position of its offset
+    #[inline]
+    fn block(&self, class: usize, offset_position: usize) -> u16 {
+        let offset = self.offsets.get_bits(offset_position, OFFSET_WIDTH[class]) as usize;
+        Self::decode(class, offset)
+    }
+
+    /// Get the number of ones before and the position of the offset of the `block_index`-th block
+    #[inline]
+    fn block_start(&self, block_index: usize) -> (usize, usize) {
+        let sample = block_index / SAMPLE_RATE;
+        let mut rank = self.rank_samples[sample];
+        let mut offset_position = self.offset_samples[sample];
+        // add up the blocks after the sample
+        for current_block in (sample * SAMPLE_RATE)..block_index {
+            let class = self.class(current_block);
+            rank += class;
+            offset_position += OFFSET_WIDTH[class];
+        }
+        (rank, offset_position)
+    }
+
+    /// Estimate the size of an RRR bit vector of length `len` with `ones` ones in `runs` runs of ones without building it
+    /// Only blocks containing the start or end of a run of ones have offsets, they are assumed to be spread randomly.
+    pub(crate) fn estimate_size(len: usize, ones: usize, runs: usize) -> usize {
+        let num_blocks = len.div_ceil(BLOCK_SIZE);
+        let density = if len == 0 { 0f64 } else { ones as f64 / len as f64 };
+        // the expected offset width of a block in which every bit is a one with probability `density`
+        let offset_width: f64 = (0..=BLOCK_SIZE)
+            .map(|class| {
+                BINOMIAL[BLOCK_SIZE][class] as f64
+                * density.powi(class as i32) * (1f64 - density).powi((BLOCK_SIZE - class) as i32)
+                * OFFSET_WIDTH[class] as f64
+            })
+            .sum();
+        let mixed_blocks = num_blocks.min(2 * runs);
+        size_of::<RrrBitVector>()
+        + BitVector::estimate_size(num_blocks * CLASS_WIDTH)
+        + BitVector::estimate_size((mixed_blocks as f64 * offset_width).ceil() as usize)
+        + 2 * num_blocks.div_ceil(SAMPLE_RATE) * size_of::<usize>()
+    }
+
+    /// Get the size of the RRR bit vector including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<RrrBitVector>()
+        + self.classes.get_size()
+        + self.offsets.get_size()
+        + self.rank_samples.capacity() * size_of::<usize>()
+        + self.offset_samples.capacity() * size_of::<usize>()
+    }
+
+    /// Get the length of the vector
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of ones in the vector
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    /// Get the bit at `index`
+    #[inline]
+    pub fn access(&self, index: usize) -> usize {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        let block_index = index / BLOCK_SIZE;
+        let (_, offset_position) = self.block_start(block_index);
+        let block = self.block(self.class(block_index), offset_position);
+        ((block >> (index % BLOCK_SIZE)) & 1) as usize
+    }
+
+    /// Get the number of ones before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    #[inline]
+    pub fn rank1(&self, index: usize) -> usize {
+        if index >= self.len {
+            return self.ones;
+        }
+        let block_index = index / BLOCK_SIZE;
+        let (rank, offset_position) = self.block_start(block_index);
+        if index.is_multiple_of(BLOCK_SIZE) {
+            return rank;
+        }
+        let block = self.block(self.class(block_index), offset_position);
+        // count the ones inside the block before index
+        rank + (block & ((1 << (index % BLOCK_SIZE)) - 1)).count_ones() as usize
+    }
+
+    /// Get the number of zeros before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    #[inline]
+    pub fn rank0(&self, index: usize) -> usize {
+        index.min(self.len) - self.rank1(index)
+    }
+
+    /// Get the position of the `index`-th zero/one, counting from zero
+    /// The last sample before the zero/one is found using binary search, after that the blocks are scanned.
+    fn select<const BIT: bool>(&self, index: usize) -> Option<usize> {
+        let total = if BIT { self.ones } else { self.len - self.ones };
+        if index >= total {
+            return None;
+        }
+        // the number of zeros/ones before the `sample`-th sample
+        let count_before = |sample: usize| if BIT {
+            self.rank_samples[sample]
+        } else {
+            sample * SAMPLE_RATE * BLOCK_SIZE - self.rank_samples[sample]
+        };
+        // find the last sample with at most `index` zeros/ones before it
+        let mut low = 0;
+        let mut high = self.rank_samples.len();
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if count_before(middle) <= index {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        let sample = low;
+        let mut remaining = index - count_before(sample);
+        let mut offset_position = self.offset_samples[sample];
+        let mut block_index = sample * SAMPLE_RATE;
+        // scan the blocks until the block containing the zero/one
+        loop {
+            let class = self.class(block_index);
+            let count = if BIT { class } else { BLOCK_SIZE - class };
+            if remaining < count {
+                let block = self.block(class, offset_position);
+                // find the `remaining`-th zero/one inside the block
+                let position = (0..BLOCK_SIZE).filter(|&j| ((block >> j) & 1 == 1) == BIT).nth(remaining).expect("No ith zero/one found in block");
+                return Some(block_index * BLOCK_SIZE + position);
+            }
+            remaining -= count;
+            offset_position += OFFSET_WIDTH[class];
+            block_index += 1;
+        }
+    }
+
+    /// Get the position of the `index`-th zero, counting from zero
+    /// Returns `None` if the vector contains at most `index` zeros.
+    #[inline]
+    pub fn select0(&self, index: usize) -> Option<usize> {
+        self.select::<false>(index)
+    }
+
+    /// Get the position of the `index`-th one, counting from zero
+    /// Returns `None` if the vector contains at most `index` ones.
+    #[inline]
+    pub fn select1(&self, index: usize) -> Option<usize> {
+        self.select::<true>(index)
+    }
+
+    /// Decompresses the RRR bit vector into a plain `BitVector`
+    pub fn to_bit_vector(&self) -> BitVector {
+        let mut offset_position = 0;
+        (0..self.len.div_ceil(BLOCK_SIZE)).flat_map(|block_index| {
+            let class = self.class(block_index);
+            let block = self.block(class, offset_position);
+            offset_position += OFFSET_WIDTH[class];
+            let width = BLOCK_SIZE.min(self.len - block_index * BLOCK_SIZE);
+            (0..width).map(move |j| (block >> j) & 1 == 1)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::test_util::clustered_bits;
+    use crate::BitVector;
+    use crate::rrr::{RrrBitVector, BINOMIAL, BLOCK_SIZE};
+
+    fn test_against_bit_vector(bit_vector: &BitVector) {
+        let rrr = RrrBitVector::from(bit_vector);
+        assert_eq!(rrr.len(), bit_vector.len());
+        assert_eq!(&rrr.to_bit_vector(), bit_vector);
+        for i in 0..bit_vector.len() {
+            assert_eq!(rrr.access(i), bit_vector.access(i));
+            assert_eq!(rrr.rank1(i), bit_vector.rank1(i));
+            assert_eq!(rrr.rank0(i), bit_vector.rank0(i));
+        }
+        assert_eq!(rrr.rank1(bit_vector.len()), bit_vector.rank1(bit_vector.len()));
+        for k in 0..=rrr.count_ones() {
+            assert_eq!(rrr.select1(k), bit_vector.select1(k));
+        }
+        for k in 0..=(rrr.len() - rrr.count_ones()) {
+            assert_eq!(rrr.select0(k), bit_vector.select0(k));
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        for block in 0..(1u16 << BLOCK_SIZE) {
+            let (class, offset) = RrrBitVector::encode(block);
+            assert_eq!(class, block.count_ones() as usize);
+            assert!(offset < BINOMIAL[BLOCK_SIZE][class]);
+            assert_eq!(RrrBitVector::decode(class, offset), block);
+        }
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let bit_vector: BitVector = (0..20000).map(|_| rng.gen_range(0..=1) == 1).collect();
+        test_against_bit_vector(&bit_vector);
+    }
+
+    #[test]
+    fn test_clustered() {
+        test_against_bit_vector(&clustered_bits(50000, 200));
+    }
+
+    #[test]
+    fn test_small() {
+        for data in ["", "0", "1", "01", "111111111111111", "0000000000000001", "1000000000000000000000000000000"] {
+            test_against_bit_vector(&BitVector::load_from_string(data));
+        }
+        assert!(RrrBitVector::new().is_empty());
+    }
+
+    #[test]
+    fn test_size() {
+        let mut bit_vector = clustered_bits(1 << 20, 1000);
+        let rrr = RrrBitVector::from(&bit_vector);
+        // smaller than the raw bits alone
+        assert!(rrr.get_size() < bit_vector.get_size());
+        bit_vector.init();
+        assert!(rrr.get_size() * 2 < bit_vector.get_size());
+    }
+}
@@ -0,0 +1,259 @@
+use std::mem::size_of;
+use crate::{BitVector, SparseBitVector};
+
+/// A bit vector storing runs of ones
+/// For `r` runs of ones two sparse bit vectors are stored:
+/// `starts` marks the position at which every run of ones starts
+/// and `ends` marks the number of ones up to and including every run of ones, minus one.
+/// Thus, the space only depends on the number of runs, not on the length of the runs.
+pub struct RunLengthBitVector {
+    /// The number of bits in the bit vector
+    len: usize,
+    /// The number of ones in the bit vector
+    ones: usize,
+    /// The start positions of the runs of ones
+    starts: SparseBitVector,
+    /// The number of ones at the end of every run of ones minus one
+    ends: SparseBitVector,
+}
+
+impl Default for RunLengthBitVector {
+    /// Creates an empty run-length encoded bit vector
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&BitVector> for RunLengthBitVector {
+    /// Encodes the runs of `bit_vector`
+    fn from(bit_vector: &BitVector) -> Self {
+        Self::from_runs(bit_vector.runs(), bit_vector.len())
+    }
+}
+
+impl RunLengthBitVector {
+    /// Creates an empty run-length encoded bit vector
+    pub fn new() -> Self {
+        Self::from_runs([], 0)
+    }
+
+    /// Creates a run-length encoded bit vector of length `len` from `(bit, start, length)` runs like the ones of `BitVector::runs`
+    /// The runs have to be ordered and must not overlap. Bits which are not covered by a run are zeros.
+    pub fn from_runs<I: IntoIterator<Item = (bool, usize, usize)>>(runs: I, len: usize) -> Self {
+        let mut starts = Vec::new();
+        let mut ends = Vec::new();
+        let mut ones = 0;
+        // the position after the last run of ones
+        let mut last_end = None;
+        for (bit, start, length) in runs {
+            if !bit || length == 0 {
+                continue;
+            }
+            assert!(start + length <= len, "Run {start}..{} is out of bounds for length {len}", start + length);
+            assert!(last_end.is_none_or(|end| start >= end), "Run {start}..{} overlaps or precedes the previous run", start + length);
+            // merge adjacent runs of ones, so every run in `starts` is preceded by a zero
+            if last_end == Some(start) {
+                ends.pop();
+            } else {
+                starts.push(start);
+            }
+            ones += length;
+            ends.push(ones - 1);
+            last_end = Some(start + length);
+        }
+        RunLengthBitVector {
+            len,
+            ones,
+            starts: SparseBitVector::from_positions(starts, len),
+            ends: SparseBitVector::from_positions(ends, ones),
+        }
+    }
+
+    /// Get the size of the run-length encoded bit vector including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<RunLengthBitVector>() - 2 * size_of::<SparseBitVector>()
+        + self.starts.get_size() + self.ends.get_size()
+    }
+
+    /// Estimate the size of a run-length encoded bit vector of length `len` with `ones` ones in `runs` runs of ones without building it
+    pub(crate) fn estimate_size(len: usize, ones: usize, runs: usize) -> usize {
+        size_of::<RunLengthBitVector>() - 2 * size_of::<SparseBitVector>()
+        + SparseBitVector::estimate_size(len, runs) + SparseBitVector::estimate_size(ones, runs)
+    }
+
+    /// Get the length of the vector
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of ones in the vector
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    /// Get the number of runs of ones
+    #[inline]
+    pub fn num_runs(&self) -> usize {
+        self.starts.count_ones()
+    }
+
+    /// Get the number of ones in the runs before the `run`-th run of ones
+    #[inline]
+    fn ones_before(&self, run: usize) -> usize {
+        if run == 0 { 0 } else { self.ends.select1(run - 1).expect("No ith run found") + 1 }
+    }
+
+    /// Get the bit at `index`
+    #[inline]
+    pub fn access(&self, index: usize) -> usize {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        self.rank1(index + 1) - self.rank1(index)
+    }
+
+    /// Get the number of ones before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    #[inline]
+    pub fn rank1(&self, index: usize) -> usize {
+        // the number of runs of ones starting before index
+        let runs = self.starts.rank1(index);
+        if runs == 0 {
+            return 0;
+        }
+        // all runs before the last one are complete, the last one may end after index
+        let run = runs - 1;
+        let start = self.starts.select1(run).expect("No ith run found");
+        let before = self.ones_before(run);
+        let length = self.ones_before(run + 1) - before;
+        before + length.min(index - start)
+    }
+
+    /// Get the number of zeros before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    #[inline]
+    pub fn rank0(&self, index: usize) -> usize {
+        index.min(self.len) - self.rank1(index)
+    }
+
+    /// Get the position of the `index`-th one, counting from zero
+    /// Returns `None` if the vector contains at most `index` ones.
+    #[inline]
+    pub fn select1(&self, index: usize) -> Option<usize> {
+        if index >= self.ones {
+            return None;
+        }
+        // the number of runs of ones which end before the `index`-th one
+        let run = self.ends.rank1(index);
+        Some(self.starts.select1(run).expect("No ith run found") + index - self.ones_before(run))
+    }
+
+    /// Get the position of the `index`-th zero, counting from zero
+    /// Returns `None` if the vector contains at most `index` zeros.
+    /// The number of runs of ones before the zero is found using binary search.
+    pub fn select0(&self, index: usize) -> Option<usize> {
+        if index >= self.len - self.ones {
+            return None;
+        }
+        // the number of zeros before the `run`-th run of ones is non-decreasing,
+        // so all runs with at most `index` zeros before them are before the `index`-th zero
+        let zeros_before = |run: usize| self.starts.select1(run).expect("No ith run found") - self.ones_before(run);
+        let mut low = 0;
+        let mut high = self.num_runs();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if zeros_before(middle) <= index {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        Some(index + self.ones_before(low))
+    }
+
+    /// Iterate over the runs of ones as `(start, length)`
+    pub fn one_runs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.num_runs()).map(|run| {
+            let start = self.starts.select1(run).expect("No ith run found");
+            (start, self.ones_before(run + 1) - self.ones_before(run))
+        })
+    }
+
+    /// Decodes the run-length encoded bit vector into a plain `BitVector`
+    pub fn to_bit_vector(&self) -> BitVector {
+        let mut runs = self.one_runs().peekable();
+        (0..self.len).map(|i| {
+            while runs.peek().is_some_and(|&(start, length)| start + length <= i) {
+                runs.next();
+            }
+            runs.peek().is_some_and(|&(start, _)| start <= i)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::test_util::clustered_bits;
+    use crate::{BitVector, RunLengthBitVector};
+
+    fn test_against_bit_vector(bit_vector: &BitVector) {
+        let run_length = RunLengthBitVector::from(bit_vector);
+        assert_eq!(run_length.len(), bit_vector.len());
+        assert_eq!(&run_length.to_bit_vector(), bit_vector);
+        assert_eq!(run_length.num_runs(), bit_vector.runs().filter(|run| run.0).count());
+        for i in 0..bit_vector.len() {
+            assert_eq!(run_length.access(i), bit_vector.access(i));
+            assert_eq!(run_length.rank1(i), bit_vector.rank1(i));
+            assert_eq!(run_length.rank0(i), bit_vector.rank0(i));
+        }
+        assert_eq!(run_length.rank1(bit_vector.len()), bit_vector.rank1(bit_vector.len()));
+        for k in 0..=run_length.count_ones() {
+            assert_eq!(run_length.select1(k), bit_vector.select1(k));
+        }
+        for k in 0..=(run_length.len() - run_length.count_ones()) {
+            assert_eq!(run_length.select0(k), bit_vector.select0(k));
+        }
+    }
+
+    #[test]
+    fn test_clustered() {
+        test_against_bit_vector(&clustered_bits(50000, 300));
+        test_against_bit_vector(&clustered_bits(10000, 3));
+    }
+
+    #[test]
+    fn test_small() {
+        for data in ["", "0", "1", "01", "10", "0110", "111111111", "000000000", "1010101"] {
+            test_against_bit_vector(&BitVector::load_from_string(data));
+        }
+        assert!(RunLengthBitVector::new().is_empty());
+    }
+
+    #[test]
+    fn test_from_runs() {
+        // adjacent runs of ones are merged and uncovered bits are zeros
+        let run_length = RunLengthBitVector::from_runs([(true, 1, 2), (true, 3, 1), (false, 4, 2), (true, 8, 2)], 12);
+        assert_eq!(run_length.to_bit_vector(), BitVector::load_from_string("011100001100"));
+        assert_eq!(run_length.num_runs(), 2);
+    }
+
+    #[test]
+    fn test_size() {
+        let mut bit_vector = clustered_bits(1 << 20, 10000);
+        bit_vector.init();
+        let run_length = RunLengthBitVector::from(&bit_vector);
+        assert!(run_length.get_size() * 10 < bit_vector.get_size());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_overlapping_runs() {
+        RunLengthBitVector::from_runs([(true, 0, 10), (true, 5, 3)], 20);
+    }
+}
@@ -1,3 +1,4 @@
+use std::cmp;
 use std::mem::size_of;
 use std::ops::Range;
 use crate::BitVector;
@@ -7,6 +8,7 @@ use crate::select_table::select_with_table;
 
 /// An accelerator used to for select operations.
 /// `BIT` specifies whether the accelerator should be used for zero = `false` or one = `true` select operations.
+#[derive(Clone)]
 pub struct SelectAccelerator<const BIT: bool> {
     // Most variables and methods have zero in the name but if `BIT = true` it means one
 
@@ -19,7 +21,11 @@ pub struct SelectAccelerator<const BIT: bool> {
     // the minimum size of a large super block
     large_super_block_size: usize,
     // the minimum size of a large block
-    large_block_size: usize
+    large_block_size: usize,
+    // the total number of zeroes/ones in the bit vector
+    zeros: usize,
+    // the start of the super block which gets the next appended zero/one
+    open_super_block_offset: usize,
 }
 
 // This is another possibility to store the block data.
@@ -55,6 +61,7 @@ pub struct SelectAccelerator<const BIT: bool> {
 // }
 
 /// A super block
+#[derive(Clone)]
 enum SuperBlock<const BIT: bool> { // Size 32 = 24 from vec + 8 through alignment and enum discriminate
     /// Large / sparse super blocks store a lookup table
     /// Large super blocks are sparse enough, so it is more efficient to simply store a lookup table
@@ -79,6 +86,7 @@ impl<const BIT: bool> SuperBlock<BIT> {
 }
 
 /// A block
+#[derive(Clone)]
 enum Block<const BIT: bool> { // Size 16 = 8 from usize / Box + 8 through alignment and enum discriminate
     /// Large / sparse blocks store a lookup table
     /// Large blocks are still sparse enough, so it is more efficient to simply store a lookup table
@@ -118,30 +126,64 @@ impl<const BIT: bool> SelectAccelerator<BIT> {
             zeros_per_block: 0,
             large_super_block_size: 0,
             large_block_size: 0,
+            zeros: 0,
+            open_super_block_offset: 0,
         }
     }
 
     /// Get the size of the select accelerator including space on the heap and the select lookup table if used
     pub fn get_size(&self) -> usize {
-        #[allow(unused_assignments)]
-        let mut table_space = 0;
-        #[cfg(feature = "USE_SELECT_TABLE")] {
-            table_space = 2 * size_of::<[[u8; 8]; 256]>();
-        }
         size_of::<SelectAccelerator<BIT>>()
         + self.super_blocks.iter().map(SuperBlock::get_size).sum::<usize>()
-        + table_space
+        + Self::table_space()
+    }
+
+    /// Get the size of the select lookup table if used
+    fn table_space() -> usize {
+        if cfg!(feature = "USE_SELECT_TABLE") { 2 * size_of::<[[u8; 8]; 256]>() } else { 0 }
+    }
+
+    /// Estimate the size of the select accelerator of a bit vector with `len` bits containing `zeros` zeros/ones without building it
+    /// The zeros/ones are assumed to be spread evenly, so clustered bits can need more space for large blocks and super blocks.
+    pub fn estimate_size(len: usize, zeros: usize) -> usize {
+        let log_len = Self::log_len(len);
+        let zeros_per_super_block = log_len.pow(2);
+        let zeros_per_block = cmp::max((log_len as f64).sqrt() as usize, 1);
+        // the average number of bits which contain a super block's or a block's zeros/ones
+        let bits_per_zero = len as f64 / cmp::max(zeros, 1) as f64;
+        let super_block_size = if bits_per_zero * zeros_per_super_block as f64 >= zeros_per_super_block.pow(2) as f64 {
+            size_of::<SuperBlock<BIT>>() + zeros_per_super_block * size_of::<usize>()
+        } else {
+            let block_size = if bits_per_zero * zeros_per_block as f64 >= log_len as f64 {
+                size_of::<Block<BIT>>() + size_of::<Vec<usize>>() + zeros_per_block * size_of::<usize>()
+            } else {
+                size_of::<Block<BIT>>()
+            };
+            size_of::<SuperBlock<BIT>>() + zeros_per_super_block.div_ceil(zeros_per_block) * block_size
+        };
+        // a vector which is not empty always has a last super block, even without zeros/ones
+        let num_super_blocks = cmp::max(zeros.div_ceil(zeros_per_super_block), cmp::min(len, 1));
+        size_of::<SelectAccelerator<BIT>>() + num_super_blocks * super_block_size + Self::table_space()
+    }
+
+    /// Get the logarithm of `len` the parameters are based on
+    fn log_len(len: usize) -> usize {
+        // For very short vectors the logarithm is zero (or does not exist), so all parameters are at least one.
+        // Otherwise, select would have to divide by zero.
+        cmp::max(len.checked_ilog2().unwrap_or(0) as usize, 1)
     }
 
     /// Initialize the select accelerator using the `bit_vector`
     pub fn init(&mut self, bit_vector: &BitVector) {
         // calculate the parameters as suggested in the lecture
-        self.zeros_per_super_block = bit_vector.len().ilog2().pow(2) as usize;
+        let log_len = Self::log_len(bit_vector.len());
+        self.zeros_per_super_block = log_len.pow(2);
         self.large_super_block_size = self.zeros_per_super_block.pow(2);
-        self.large_block_size = bit_vector.len().ilog2() as usize;
-        self.zeros_per_block = (self.large_block_size as f64).sqrt() as usize;
+        self.large_block_size = log_len;
+        self.zeros_per_block = cmp::max((self.large_block_size as f64).sqrt() as usize, 1);
         let mut current_super_block_offset = 0;
         let mut next_super_block_offset;
+        let mut last_super_block_offset = 0;
 
         let mut zeroes = 0;
         for i in 0..bit_vector.len() {
@@ -150,6 +192,7 @@ impl<const BIT: bool> SelectAccelerator<BIT> {
             if zeroes != self.zeros_per_super_block && i != bit_vector.len()-1 {
                 continue;
             }
+            self.zeros += zeroes;
             // if we found enough zeroes/ones for a super block or the bit vector ends construct a new super block
             next_super_block_offset = i+1;
             // either create a small or large super block depending on the size of the super block which is the difference between the `current_super_block_offset` and the `next_super_block_offset`
@@ -159,13 +202,58 @@ impl<const BIT: bool> SelectAccelerator<BIT> {
                 self.super_blocks.push(self.create_small_super_block(bit_vector, current_super_block_offset..next_super_block_offset));
             }
             zeroes = 0;
+            last_super_block_offset = current_super_block_offset;
             current_super_block_offset = next_super_block_offset;
         }
+        // appended zeroes/ones go into the last super block if it is not full yet
+        self.open_super_block_offset = if self.super_blocks.len() > self.zeros / self.zeros_per_super_block {
+            last_super_block_offset
+        } else {
+            current_super_block_offset
+        };
 
         // Testing has shown that it is faster to shrink the `super_blocks` vector than to loop through the bit vector and calculating the number of super blocks in advance.
         self.super_blocks.shrink_to_fit();
     }
 
+    /// Update the select accelerator after the last bit of the `bit_vector` was appended
+    /// The parameters depend on the logarithm of the length, so the accelerator is rebuilt whenever the length reaches a power of two.
+    /// Otherwise, the last super block is kept as lookup table until it is full and only then converted into a large or small super block.
+    /// Every bit is scanned once for that conversion, which makes appending amortised constant time.
+    pub fn push(&mut self, bit_vector: &BitVector) {
+        if bit_vector.len().is_power_of_two() {
+            *self = Self::new();
+            self.init(bit_vector);
+            return;
+        }
+        let index = bit_vector.len() - 1;
+        if bit_vector.access(index) != BIT as usize {
+            // select never looks at the bits after the last zero/one
+            return;
+        }
+        if self.super_blocks.len() == self.zeros / self.zeros_per_super_block {
+            self.super_blocks.push(LargeSuperBlock { select_table: Vec::new() });
+        }
+        let super_block = self.super_blocks.last_mut().unwrap();
+        if let SmallSuperBlock { .. } = super_block {
+            // the last super block of `init` may be small although it is not full
+            *super_block = LargeSuperBlock { select_table: Self::calc_select_table(bit_vector, self.open_super_block_offset..index) };
+        }
+        if let LargeSuperBlock { select_table } = super_block {
+            select_table.push(index);
+        }
+        self.zeros += 1;
+        if self.zeros.is_multiple_of(self.zeros_per_super_block) {
+            let super_block_range = self.open_super_block_offset..index + 1;
+            *self.super_blocks.last_mut().unwrap() = if super_block_range.len() >= self.large_super_block_size {
+                self.create_large_super_block(bit_vector, super_block_range)
+            } else {
+                self.create_small_super_block(bit_vector, super_block_range)
+            };
+            self.open_super_block_offset = index + 1;
+        }
+    }
+
     /// Creates a lookup table for the bits inside `range` inside `bit_vector`
     /// the i-th entry in the vec holds the global indices in the `bit_vector` to the i-th zero/one inside the `range`
     fn calc_select_table(bit_vector: &BitVector, range: Range<usize>) -> Vec<usize> {
@@ -220,14 +308,18 @@ impl<const BIT: bool> SelectAccelerator<BIT> {
     }
 
     /// Get the position of the `index`-th zero/one inside the `bit_vector`
+    /// `index` is zero based, `None` is returned if there are not more than `index` zeroes/ones.
     #[inline]
-    pub fn select(&self, index: usize, bit_vector: &BitVector) -> usize {
+    pub fn select(&self, index: usize, bit_vector: &BitVector) -> Option<usize> {
+        if index >= self.zeros {
+            return None;
+        }
         let super_block_index = index / self.zeros_per_super_block;
         match &self.super_blocks[super_block_index] {
             // If the super block is large simply return the lookup table result.
             // We have to adjust the index, so it requests the i-th zero/one inside the current super block.
             // Because we store the global index inside the lookup table we don't have to do anymore calculations or store the index of the super block itself.
-            LargeSuperBlock{ select_table} => select_table[index % self.zeros_per_super_block],
+            LargeSuperBlock{ select_table} => Some(select_table[index % self.zeros_per_super_block]),
             // If the super block is small calculate the block index and look inside it
             SmallSuperBlock{ blocks } => {
                 let block_index = (index % self.zeros_per_super_block) / self.zeros_per_block;
@@ -235,14 +327,14 @@ impl<const BIT: bool> SelectAccelerator<BIT> {
                     // If the block is large simply return the lookup table result.
                     // We have to adjust the index, so it requests the i-th zero/one inside the current block.
                     // Because we store the global index inside the lookup table we don't have to do anymore calculations or store the index of the block itself.
-                    LargeBlock{ select_table} => select_table[(index % self.zeros_per_super_block) % self.zeros_per_block],
+                    LargeBlock{ select_table} => Some(select_table[(index % self.zeros_per_super_block) % self.zeros_per_block]),
                     // If the block is small first get the block from the bit_vector using the `offset`.
                     // After that we get the index using the select lookup table.
                     // We have to adjust the index, so it requests the i-th zero/one inside the current block.
                     // We also have to add the offset of the block inside the bit vector because select_with_table will only return a local starting at the start of the block
                     SmallBlock{ offset} => {
-                        offset
-                            + select_with_table(BIT, bit_vector.access_block(*offset), (index % self.zeros_per_super_block) % self.zeros_per_block).expect("No ith zero/one found in block")
+                        Some(offset
+                            + select_with_table(BIT, bit_vector.access_block(*offset), (index % self.zeros_per_super_block) % self.zeros_per_block).expect("No ith zero/one found in block"))
                     }
                 }
             },
@@ -272,7 +364,7 @@ pub mod test {
         let mut bit_vector = BitVector::load_from_string(&data);
         bit_vector.init_select_structures();
 
-        let select_accelerator_0 = bit_vector.select_accelerator_0.as_ref().unwrap();
+        let select_accelerator_0 = bit_vector.select_accelerator_0.get().unwrap();
 
         let mut zeroes = 0;
         let mut super_block_index = 0;
@@ -313,7 +405,7 @@ pub mod test {
         let mut bit_vector = BitVector::load_from_string(&data);
         bit_vector.init_select_structures();
 
-        let select_accelerator_0 = bit_vector.select_accelerator_0.as_ref().unwrap();
+        let select_accelerator_0 = bit_vector.select_accelerator_0.get().unwrap();
 
         let mut zeroes = 0;
         let mut super_block_index = 0;
@@ -76,8 +76,8 @@ fn generate_table(bit: bool) -> [[u8; 8]; 256] {
     // Loop through all 256 possible values
     for (i, data_entry) in table.iter_mut().enumerate() {
         // Loop through all possible indices inside the byte
-        for j in 0..8 {
-            data_entry[j] = generate_table_helper(bit, i, j).unwrap_or(NOT_FOUND as usize) as u8;
+        for (j, entry) in data_entry.iter_mut().enumerate() {
+            *entry = generate_table_helper(bit, i, j).unwrap_or(NOT_FOUND as usize) as u8;
         }
     }
     table
@@ -0,0 +1,317 @@
+use std::mem::size_of;
+use crate::BitVector;
+use crate::select::SelectAccelerator;
+
+/// A sparse bit vector using the Elias-Fano encoding
+/// The positions of the ones are split into high and low parts.
+/// The low `low_width` bits of every position are stored packed inside `low`.
+/// The high parts are stored unary coded inside `high`: the `k`-th one is stored as a one at `(position >> low_width) + k`,
+/// so the number of zeros before it is its high part.
+/// For `m` ones in a vector of length `n` this requires about `m * (2 + log(n / m))` bits
+/// plus the select accelerators of `high`.
+pub struct SparseBitVector {
+    /// The number of bits in the bit vector
+    len: usize,
+    /// The number of ones in the bit vector
+    ones: usize,
+    /// The number of low bits per position
+    low_width: usize,
+    /// The unary coded high parts
+    high: BitVector,
+    /// The packed low parts
+    low: BitVector,
+}
+
+impl Default for SparseBitVector {
+    /// Creates an empty sparse bit vector
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&BitVector> for SparseBitVector {
+    /// Encodes the ones of `bit_vector`
+    fn from(bit_vector: &BitVector) -> Self {
+        Self::from_positions((0..bit_vector.len()).filter(|&i| bit_vector.access(i) == 1), bit_vector.len())
+    }
+}
+
+impl SparseBitVector {
+    /// Creates an empty sparse bit vector
+    pub fn new() -> Self {
+        Self::from_positions([], 0)
+    }
+
+    /// Creates a sparse bit vector of length `len` with ones at the `positions`
+    /// `positions` has to be strictly increasing and every position has to be less than `len`.
+    pub fn from_positions<I: IntoIterator<Item = usize>>(positions: I, len: usize) -> Self {
+        let positions: Vec<usize> = positions.into_iter().collect();
+        for (i, &position) in positions.iter().enumerate() {
+            assert!(position < len, "Position {position} is out of bounds for length {len}");
+            assert!(i == 0 || positions[i - 1] < position, "Positions are not strictly increasing");
+        }
+        let ones = positions.len();
+        let low_width = Self::low_width(len, ones);
+
+        // the high parts are unary coded, every high part increment is a zero and every position a one
+        let mut high_bits = Vec::with_capacity(ones + (len >> low_width) + 1);
+        let mut bucket = 0;
+        for &position in &positions {
+            while bucket < position >> low_width {
+                high_bits.push(false);
+                bucket += 1;
+            }
+            high_bits.push(true);
+        }
+        // close all remaining buckets, so there is a zero for every possible high part
+        while bucket <= len >> low_width {
+            high_bits.push(false);
+            bucket += 1;
+        }
+        let mut high: BitVector = high_bits.into_iter().collect();
+        high.init_select_structures();
+
+        let low = positions.iter()
+            .flat_map(|&position| (0..low_width).map(move |j| (position >> j) & 1 == 1))
+            .collect();
+
+        SparseBitVector {
+            len,
+            ones,
+            low_width,
+            high,
+            low,
+        }
+    }
+
+    /// Get the number of low bits per position for `ones` ones in a vector of length `len`
+    fn low_width(len: usize, ones: usize) -> usize {
+        // choose the number of low bits as suggested by Elias and Fano
+        if ones == 0 || len <= ones { 0 } else { (len / ones).ilog2() as usize }
+    }
+
+    /// Get the size of the sparse bit vector including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<SparseBitVector>() + self.high.get_size() + self.low.get_size()
+    }
+
+    /// Estimate the size of a sparse bit vector of length `len` with `ones` ones without building it
+    pub(crate) fn estimate_size(len: usize, ones: usize) -> usize {
+        let low_width = Self::low_width(len, ones);
+        let high_len = ones + (len >> low_width) + 1;
+        size_of::<SparseBitVector>()
+        + BitVector::estimate_size(high_len)
+        + SelectAccelerator::<false>::estimate_size(high_len, high_len - ones)
+        + SelectAccelerator::<true>::estimate_size(high_len, ones)
+        + BitVector::estimate_size(ones * low_width)
+    }
+
+    /// Get the length of the vector
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of ones in the vector
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    /// Get the low part of the `index`-th position
+    #[inline]
+    fn low(&self, index: usize) -> usize {
+        self.low.get_bits(index * self.low_width, self.low_width) as usize
+    }
+
+    /// Get the position of the `index`-th one without checking whether it exists
+    #[inline]
+    fn position(&self, index: usize) -> usize {
+        let high = self.high.select1(index).expect("No ith one found") - index;
+        (high << self.low_width) | self.low(index)
+    }
+
+    /// Get the bit at `index`
+    #[inline]
+    pub fn access(&self, index: usize) -> usize {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        if self.next(index) == Some(index) { 1 } else { 0 }
+    }
+
+    /// Get the number of ones before `index`, i.e. in `0..index`
+    #[inline]
+    pub fn rank1(&self, index: usize) -> usize {
+        if index >= self.len {
+            return self.ones;
+        }
+        let high = index >> self.low_width;
+        let low = index & ((1 << self.low_width) - 1);
+        // the ones of bucket `high` start after the `high`-th zero in `high`
+        let mut position = if high == 0 { 0 } else { self.high.select0(high - 1).expect("No ith zero found") + 1 };
+        // all ones before are in lower buckets
+        let mut result = position - high;
+        // count the ones of the bucket whose low part is less than the one of `index`
+        while position < self.high.len() && self.high.access(position) == 1 && self.low(result) < low {
+            position += 1;
+            result += 1;
+        }
+        result
+    }
+
+    /// Get the number of zeros before `index`, i.e. in `0..index`
+    #[inline]
+    pub fn rank0(&self, index: usize) -> usize {
+        index.min(self.len) - self.rank1(index)
+    }
+
+    /// Get the position of the `index`-th one, counting from zero
+    /// Returns `None` if the vector contains at most `index` ones.
+    #[inline]
+    pub fn select1(&self, index: usize) -> Option<usize> {
+        if index >= self.ones {
+            return None;
+        }
+        Some(self.position(index))
+    }
+
+    /// Get the position of the `index`-th zero, counting from zero
+    /// Returns `None` if the vector contains at most `index` zeros.
+    /// The zeros are not encoded, so this uses a binary search over the ones.
+    pub fn select0(&self, index: usize) -> Option<usize> {
+        if index >= self.len - self.ones {
+            return None;
+        }
+        // `position(k) - k` is the number of zeros before the `k`-th one,
+        // so the number of ones before the `index`-th zero is the number of ones with at most `index` zeros before them
+        let mut low = 0;
+        let mut high = self.ones;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.position(middle) - middle <= index {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        Some(index + low)
+    }
+
+    /// Get the position of the first one at or after `index`
+    #[inline]
+    pub fn next(&self, index: usize) -> Option<usize> {
+        self.select1(self.rank1(index))
+    }
+
+    /// Get the position of the last one at or before `index`
+    #[inline]
+    pub fn prev(&self, index: usize) -> Option<usize> {
+        match self.rank1(index.saturating_add(1)) {
+            0 => None,
+            ones => self.select1(ones - 1),
+        }
+    }
+
+    /// Iterate over the positions of the ones
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.ones).map(|k| self.position(k))
+    }
+
+    /// Decodes the sparse bit vector into a plain `BitVector`
+    pub fn to_bit_vector(&self) -> BitVector {
+        let mut positions = self.iter().peekable();
+        (0..self.len).map(|i| {
+            if positions.peek() == Some(&i) {
+                positions.next();
+                true
+            } else {
+                false
+            }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::{BitVector, SparseBitVector};
+
+    fn random_positions(len: usize, one_in: usize) -> Vec<usize> {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        (0..len).filter(|_| rng.gen_range(0..one_in) == 0).collect()
+    }
+
+    fn test_against_bit_vector(positions: &[usize], len: usize) {
+        let sparse = SparseBitVector::from_positions(positions.iter().copied(), len);
+        let bit_vector = sparse.to_bit_vector();
+        assert_eq!(bit_vector.len(), len);
+        assert_eq!(sparse.count_ones(), positions.len());
+        assert_eq!(sparse.iter().collect::<Vec<usize>>(), positions);
+        for i in 0..len {
+            assert_eq!(sparse.access(i), bit_vector.access(i));
+            assert_eq!(sparse.rank1(i), bit_vector.rank1(i));
+            assert_eq!(sparse.rank0(i), bit_vector.rank0(i));
+            assert_eq!(sparse.next(i), positions.iter().copied().find(|&p| p >= i));
+            assert_eq!(sparse.prev(i), positions.iter().copied().rev().find(|&p| p <= i));
+        }
+        assert_eq!(sparse.rank1(len), positions.len());
+        for k in 0..=positions.len() {
+            assert_eq!(sparse.select1(k), bit_vector.select1(k));
+        }
+        for k in 0..=(len - positions.len()) {
+            assert_eq!(sparse.select0(k), bit_vector.select0(k));
+        }
+    }
+
+    #[test]
+    fn test_sparse() {
+        test_against_bit_vector(&random_positions(100000, 1000), 100000);
+    }
+
+    #[test]
+    fn test_dense() {
+        test_against_bit_vector(&random_positions(10000, 2), 10000);
+        test_against_bit_vector(&(0..1000).collect::<Vec<usize>>(), 1000);
+    }
+
+    #[test]
+    fn test_small() {
+        test_against_bit_vector(&[], 0);
+        test_against_bit_vector(&[], 1);
+        test_against_bit_vector(&[0], 1);
+        test_against_bit_vector(&[], 100);
+        test_against_bit_vector(&[0, 99], 100);
+        test_against_bit_vector(&[63, 64, 65], 129);
+        assert!(SparseBitVector::new().is_empty());
+    }
+
+    #[test]
+    fn test_from_bit_vector() {
+        let data = "010010001010100001111010101011111001000010111000111000110101010011010100101010111110000110101101010101111101010101110000111011100110110101110101111";
+        let bit_vector = BitVector::load_from_string(data);
+        let sparse = SparseBitVector::from(&bit_vector);
+        assert_eq!(sparse.to_bit_vector(), bit_vector);
+    }
+
+    #[test]
+    fn test_size() {
+        let len = 1 << 20;
+        let sparse = SparseBitVector::from_positions(random_positions(len, 10000), len);
+        let mut bit_vector = sparse.to_bit_vector();
+        bit_vector.init_select_structures();
+        assert!(sparse.get_size() * 10 < bit_vector.get_size());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unsorted_positions() {
+        SparseBitVector::from_positions([5, 3], 10);
+    }
+}
@@ -0,0 +1,174 @@
+use std::mem::size_of;
+use crate::BitVector;
+
+/// A vector of length `len` which only stores values at some positions
+/// A `BitVector` marks the positions with a value and the values are stored densely in their order.
+/// The value at a position is found with rank and the position of a value with select.
+#[derive(Clone)]
+pub struct SparseVec<T> {
+    /// Whether there is a value at a position
+    present: BitVector,
+    /// The values ordered by position
+    values: Vec<T>,
+}
+
+impl<T> FromIterator<(usize, T)> for SparseVec<T> {
+    /// Creates a sparse vector of the `(index, value)` pairs of `iter` which ends after the largest index
+    fn from_iter<I: IntoIterator<Item = (usize, T)>>(iter: I) -> Self {
+        let pairs: Vec<(usize, T)> = iter.into_iter().collect();
+        let len = pairs.iter().map(|&(index, _)| index + 1).max().unwrap_or(0);
+        Self::new(len, pairs)
+    }
+}
+
+impl<T> SparseVec<T> {
+    /// Creates a sparse vector of length `len` with the values of the `(index, value)` pairs
+    /// The pairs may be in any order, but every index must be less than `len` and occur only once.
+    pub fn new(len: usize, pairs: impl IntoIterator<Item = (usize, T)>) -> Self {
+        let mut pairs: Vec<(usize, T)> = pairs.into_iter().collect();
+        pairs.sort_by_key(|&(index, _)| index);
+        let mut present = BitVector::new();
+        present.resize(len);
+        for (i, &(index, _)) in pairs.iter().enumerate() {
+            assert!(index < len, "index out of bounds: the len is {len} but the index is {index}");
+            assert!(i == 0 || pairs[i - 1].0 != index, "index {index} occurs more than once");
+            present.set(index, true);
+        }
+        present.init();
+        SparseVec {
+            present,
+            values: pairs.into_iter().map(|(_, value)| value).collect(),
+        }
+    }
+
+    /// Get the size of the sparse vector including space on the heap
+    /// Only the values themselves are counted and not the heap space they use.
+    pub fn get_size(&self) -> usize {
+        size_of::<SparseVec<T>>() + self.present.get_size() + self.values.capacity() * size_of::<T>()
+    }
+
+    /// Get the length of the vector including the positions without a value
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.present.len()
+    }
+
+    /// Whether the vector has length zero
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.present.is_empty()
+    }
+
+    /// Get the number of stored values
+    #[inline]
+    pub fn num_values(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Get the stored values ordered by position
+    #[inline]
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Get the bit vector marking the positions with a value
+    #[inline]
+    pub fn presence(&self) -> &BitVector {
+        &self.present
+    }
+
+    /// Get the index of the value at `index` among the stored values
+    /// Returns `None` if there is no value at `index` or `index` is out of bounds.
+    #[inline]
+    pub fn dense_index(&self, index: usize) -> Option<usize> {
+        (index < self.len() && self.present.access(index) == 1).then(|| self.present.rank1(index))
+    }
+
+    /// Get the position of the `dense`-th stored value
+    #[inline]
+    pub fn sparse_index(&self, dense: usize) -> usize {
+        assert!(dense < self.num_values(), "index out of bounds: the len is {} but the index is {dense}", self.num_values());
+        self.present.select1(dense).expect("No ith value found")
+    }
+
+    /// Get the value at `index`
+    /// Returns `None` if there is no value at `index` or `index` is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.dense_index(index).map(|dense| &self.values[dense])
+    }
+
+    /// Get the value at `index` mutably
+    /// Returns `None` if there is no value at `index` or `index` is out of bounds.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.dense_index(index).map(|dense| &mut self.values[dense])
+    }
+
+    /// Iterate over the stored values together with their positions in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> + '_ {
+        self.values.iter().enumerate().map(|(dense, value)| (self.sparse_index(dense), value))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::SparseVec;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let naive: Vec<Option<u32>> = (0..5000).map(|_| if rng.gen_range(0..10) == 0 { Some(rng.gen()) } else { None }).collect();
+        let pairs: Vec<(usize, u32)> = naive.iter().enumerate().filter_map(|(i, value)| value.map(|value| (i, value))).collect();
+        // the pairs do not have to be sorted
+        let mut shuffled = pairs.clone();
+        shuffled.reverse();
+        let sparse = SparseVec::new(naive.len(), shuffled);
+        assert_eq!(sparse.len(), naive.len());
+        assert_eq!(sparse.num_values(), pairs.len());
+        for (i, value) in naive.iter().enumerate() {
+            assert_eq!(sparse.get(i), value.as_ref());
+        }
+        for (dense, &(index, value)) in pairs.iter().enumerate() {
+            assert_eq!(sparse.dense_index(index), Some(dense));
+            assert_eq!(sparse.sparse_index(dense), index);
+            assert_eq!(sparse.values()[dense], value);
+        }
+        assert_eq!(sparse.iter().map(|(i, &value)| (i, value)).collect::<Vec<(usize, u32)>>(), pairs);
+    }
+
+    #[test]
+    fn test_small() {
+        let mut sparse: SparseVec<String> = [(7, "b".to_string()), (2, "a".to_string())].into_iter().collect();
+        assert_eq!(sparse.len(), 8);
+        assert_eq!(sparse.get(2).map(String::as_str), Some("a"));
+        assert_eq!(sparse.get(3), None);
+        assert_eq!(sparse.dense_index(3), None);
+        sparse.get_mut(7).unwrap().push('c');
+        assert_eq!(sparse.iter().map(|(i, value)| (i, value.as_str())).collect::<Vec<_>>(), vec![(2, "a"), (7, "bc")]);
+        let empty: SparseVec<u8> = SparseVec::new(10, []);
+        assert_eq!(empty.iter().count(), 0);
+        assert_eq!(empty.get(9), None);
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let mut sparse = SparseVec::new(64, [(63, 'a')]);
+        assert_eq!(sparse.get(63), Some(&'a'));
+        for index in [64, 65, 1000, usize::MAX] {
+            assert_eq!(sparse.dense_index(index), None);
+            assert_eq!(sparse.get(index), None);
+            assert_eq!(sparse.get_mut(index), None);
+        }
+        assert_eq!(SparseVec::<u8>::new(0, []).get(0), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_duplicate_index() {
+        SparseVec::new(5, [(1, 'a'), (1, 'b')]);
+    }
+}
@@ -0,0 +1,17 @@
+use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use crate::BitVector;
+
+/// Generates bits in runs of random length between one and `max_run`
+pub fn clustered_bits(len: usize, max_run: usize) -> BitVector {
+    let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+    let mut bits = Vec::with_capacity(len);
+    let mut bit = rng.gen_range(0..=1) == 1;
+    while bits.len() < len {
+        let run = rng.gen_range(1..=max_run).min(len - bits.len());
+        bits.extend(std::iter::repeat_n(bit, run));
+        bit = !bit;
+    }
+    bits.into_iter().collect()
+}
@@ -0,0 +1,164 @@
+use crate::{AdaptiveBitVector, BitVector, DynamicBitVector, IndexedBitVector, RrrBitVector, RunLengthBitVector, SparseBitVector};
+
+/// Read access to the bits of a bit vector
+pub trait BitAccess {
+    /// Get the length of the vector
+    fn len(&self) -> usize;
+
+    /// Whether the vector is empty
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the bit at `index`
+    fn access(&self, index: usize) -> usize;
+}
+
+/// Rank queries on a bit vector
+pub trait Rank: BitAccess {
+    /// Get the number of ones before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    fn rank1(&self, index: usize) -> usize;
+
+    /// Get the number of zeros before `index`, i.e. in `0..index`
+    /// `index` may be equal to the length of the vector.
+    fn rank0(&self, index: usize) -> usize {
+        index - self.rank1(index)
+    }
+
+    /// Get the number of ones in the vector
+    fn count_ones(&self) -> usize {
+        self.rank1(self.len())
+    }
+}
+
+/// Zero select queries on a bit vector
+pub trait Select0: BitAccess {
+    /// Get the position of the `index`-th zero, counting from zero
+    /// Returns `None` if the vector contains at most `index` zeros.
+    fn select0(&self, index: usize) -> Option<usize>;
+}
+
+/// One select queries on a bit vector
+pub trait Select1: BitAccess {
+    /// Get the position of the `index`-th one, counting from zero
+    /// Returns `None` if the vector contains at most `index` ones.
+    fn select1(&self, index: usize) -> Option<usize>;
+}
+
+/// Rank and both select queries on a bit vector
+/// This is implemented for every type implementing the single traits.
+pub trait RankSelect: Rank + Select0 + Select1 {}
+
+impl<T: Rank + Select0 + Select1> RankSelect for T {}
+
+/// The space used by a data structure
+pub trait SpaceUsage {
+    /// Get the size including space on the heap
+    fn get_size(&self) -> usize;
+}
+
+/// Implements all traits for bit vector types by forwarding to their inherent methods
+macro_rules! impl_bit_vector_traits {
+    ($($bit_vector:ty),*) => {$(
+        impl BitAccess for $bit_vector {
+            #[inline]
+            fn len(&self) -> usize {
+                <$bit_vector>::len(self)
+            }
+
+            #[inline]
+            fn access(&self, index: usize) -> usize {
+                <$bit_vector>::access(self, index)
+            }
+        }
+
+        impl Rank for $bit_vector {
+            #[inline]
+            fn rank1(&self, index: usize) -> usize {
+                <$bit_vector>::rank1(self, index)
+            }
+
+            #[inline]
+            fn rank0(&self, index: usize) -> usize {
+                <$bit_vector>::rank0(self, index)
+            }
+        }
+
+        impl Select0 for $bit_vector {
+            #[inline]
+            fn select0(&self, index: usize) -> Option<usize> {
+                <$bit_vector>::select0(self, index)
+            }
+        }
+
+        impl Select1 for $bit_vector {
+            #[inline]
+            fn select1(&self, index: usize) -> Option<usize> {
+                <$bit_vector>::select1(self, index)
+            }
+        }
+
+        impl SpaceUsage for $bit_vector {
+            #[inline]
+            fn get_size(&self) -> usize {
+                <$bit_vector>::get_size(self)
+            }
+        }
+    )*};
+}
+
+impl_bit_vector_traits!(BitVector, IndexedBitVector, SparseBitVector, RrrBitVector, RunLengthBitVector, AdaptiveBitVector, DynamicBitVector);
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::{BitAccess, BitVector, DynamicBitVector, RankSelect, RrrBitVector, RunLengthBitVector, SpaceUsage, SparseBitVector};
+
+    /// Checks a generic bit vector against `reference`
+    fn test_generic<T: RankSelect + SpaceUsage>(bit_vector: &T, reference: &BitVector) {
+        assert_eq!(BitAccess::len(bit_vector), reference.len());
+        assert_eq!(bit_vector.count_ones(), reference.rank1(reference.len()));
+        assert!(bit_vector.get_size() > 0);
+        for i in 0..reference.len() {
+            assert_eq!(BitAccess::access(bit_vector, i), reference.access(i));
+            assert_eq!(bit_vector.rank0(i), reference.rank0(i));
+            assert_eq!(bit_vector.rank1(i), reference.rank1(i));
+        }
+        for k in 0..=reference.rank1(reference.len()) {
+            assert_eq!(bit_vector.select1(k), reference.select1(k));
+        }
+        for k in 0..=reference.rank0(reference.len()) {
+            assert_eq!(bit_vector.select0(k), reference.select0(k));
+        }
+    }
+
+    #[test]
+    fn test_all_representations() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let reference: BitVector = (0..5000).map(|_| rng.gen_range(0..8) == 0).collect();
+        test_generic(&reference, &reference);
+        test_generic(&reference.clone().build(), &reference);
+        test_generic(&SparseBitVector::from(&reference), &reference);
+        test_generic(&RrrBitVector::from(&reference), &reference);
+        test_generic(&RunLengthBitVector::from(&reference), &reference);
+        test_generic(&DynamicBitVector::from(&reference), &reference);
+    }
+
+    #[test]
+    fn test_trait_objects() {
+        let reference = BitVector::load_from_string("0100100010101000011110101");
+        let bit_vectors: Vec<Box<dyn RankSelect>> = vec![
+            Box::new(reference.clone()),
+            Box::new(SparseBitVector::from(&reference)),
+            Box::new(RrrBitVector::from(&reference)),
+            Box::new(RunLengthBitVector::from(&reference)),
+        ];
+        for bit_vector in &bit_vectors {
+            assert_eq!(bit_vector.rank1(10), reference.rank1(10));
+            assert_eq!(bit_vector.select0(3), reference.select0(3));
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::Range;
+use crate::{BitVector, IndexedBitVector, SparseBitVector, Symbol};
+use crate::wavelet_tree::bits_for;
+
+/// A wavelet matrix over a sequence of symbols
+/// Level `l` contains the `l`-th most significant bit of every symbol. Before building the next level
+/// the symbols are stably partitioned by this bit, all zeros first.
+/// In contrast to the `WaveletTree` the nodes of a level are not stored in order, but no node boundaries are needed:
+/// a position `i` is mapped to `rank0(i)` if its bit is zero and to `zeros + rank1(i)` otherwise.
+pub struct WaveletMatrix<T: Symbol> {
+    /// The number of symbols
+    len: usize,
+    /// One bit vector per bit of the symbols, starting with the most significant bit
+    levels: Vec<IndexedBitVector>,
+    /// The number of zeros on every level
+    zeros: Vec<usize>,
+    /// The stored symbol type
+    symbol: PhantomData<T>,
+}
+
+impl<T: Symbol> WaveletMatrix<T> {
+    /// Creates a wavelet matrix of `symbols`
+    pub fn new(symbols: &[T]) -> Self {
+        let max = symbols.iter().map(|symbol| symbol.to_u64()).max().unwrap_or(0);
+        let num_levels = bits_for(max);
+        let mut current: Vec<u64> = symbols.iter().map(|symbol| symbol.to_u64()).collect();
+        let mut levels = Vec::with_capacity(num_levels);
+        let mut zeros = Vec::with_capacity(num_levels);
+        for level in 0..num_levels {
+            let shift = num_levels - level - 1;
+            let bit_vector = current.iter().map(|&symbol| (symbol >> shift) & 1 == 1).collect::<BitVector>().build();
+            zeros.push(bit_vector.rank0(bit_vector.len()));
+            levels.push(bit_vector);
+            current.sort_by_key(|&symbol| (symbol >> shift) & 1);
+        }
+        WaveletMatrix {
+            len: symbols.len(),
+            levels,
+            zeros,
+            symbol: PhantomData,
+        }
+    }
+
+    /// Get the size of the wavelet matrix including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<WaveletMatrix<T>>()
+        + self.levels.iter().map(|level| size_of::<IndexedBitVector>() + level.get_size()).sum::<usize>()
+        + self.zeros.capacity() * size_of::<usize>()
+    }
+
+    /// Get the number of symbols
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no symbols
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of bits per symbol
+    #[inline]
+    fn num_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Get the bit of `symbol` on `level`
+    #[inline]
+    fn bit(&self, symbol: u64, level: usize) -> bool {
+        (symbol >> (self.num_levels() - level - 1)) & 1 == 1
+    }
+
+    /// Map `index` on `level` to the next level following `bit`
+    #[inline]
+    fn map(&self, level: usize, index: usize, bit: bool) -> usize {
+        if bit {
+            self.zeros[level] + self.levels[level].rank1(index)
+        } else {
+            self.levels[level].rank0(index)
+        }
+    }
+
+    /// Map `range` on `level` to the next level following `bit`
+    #[inline]
+    fn map_range(&self, level: usize, range: &Range<usize>, bit: bool) -> Range<usize> {
+        self.map(level, range.start, bit)..self.map(level, range.end, bit)
+    }
+
+    /// Clamp `range` to the symbols
+    #[inline]
+    fn clamp(&self, range: Range<usize>) -> Range<usize> {
+        range.start.min(self.len)..range.end.min(self.len)
+    }
+
+    /// Get the symbol at `index`
+    pub fn access(&self, index: usize) -> T {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        let mut index = index;
+        let mut symbol = 0;
+        for level in 0..self.num_levels() {
+            let bit = self.levels[level].access(index) == 1;
+            symbol = (symbol << 1) | bit as u64;
+            index = self.map(level, index, bit);
+        }
+        T::from_u64(symbol)
+    }
+
+    /// Get the number of occurrences of `symbol` before `index`, i.e. in `0..index`
+    pub fn rank(&self, symbol: T, index: usize) -> usize {
+        let symbol = symbol.to_u64();
+        if bits_for(symbol) > self.num_levels() {
+            return 0;
+        }
+        let mut range = 0..index.min(self.len);
+        for level in 0..self.num_levels() {
+            range = self.map_range(level, &range, self.bit(symbol, level));
+        }
+        range.len()
+    }
+
+    /// Get the position of the `index`-th occurrence of `symbol`, counting from zero
+    /// Returns `None` if `symbol` occurs at most `index` times.
+    pub fn select(&self, symbol: T, index: usize) -> Option<usize> {
+        let symbol = symbol.to_u64();
+        if bits_for(symbol) > self.num_levels() {
+            return None;
+        }
+        // all occurrences of the symbol end up next to each other on the last level
+        let mut range = 0..self.len;
+        for level in 0..self.num_levels() {
+            range = self.map_range(level, &range, self.bit(symbol, level));
+        }
+        if index >= range.len() {
+            return None;
+        }
+        let mut position = range.start + index;
+        for level in (0..self.num_levels()).rev() {
+            let bit_vector = &self.levels[level];
+            position = if self.bit(symbol, level) {
+                bit_vector.select1(position - self.zeros[level])
+            } else {
+                bit_vector.select0(position)
+            }.expect("No ith zero/one found");
+        }
+        Some(position)
+    }
+
+    /// Get the number of symbols in `range` which are smaller than `value`
+    fn count_less(&self, range: Range<usize>, value: u64) -> usize {
+        if bits_for(value) > self.num_levels() {
+            return range.len();
+        }
+        let mut range = range;
+        let mut count = 0;
+        for level in 0..self.num_levels() {
+            let bit = self.bit(value, level);
+            if bit {
+                // all symbols going left are smaller
+                count += self.map_range(level, &range, false).len();
+            }
+            range = self.map_range(level, &range, bit);
+        }
+        count
+    }
+
+    /// Get the number of symbols in `range` which are inside `values`
+    pub fn range_count(&self, range: Range<usize>, values: Range<T>) -> usize {
+        let range = self.clamp(range);
+        let values = values.start.to_u64()..values.end.to_u64();
+        if values.is_empty() {
+            return 0;
+        }
+        self.count_less(range.clone(), values.end) - self.count_less(range, values.start)
+    }
+
+    /// Get the smallest symbol in `range` which is at least `value`
+    pub fn next_value(&self, range: Range<usize>, value: T) -> Option<T> {
+        let value = value.to_u64();
+        if bits_for(value) > self.num_levels() {
+            return None;
+        }
+        self.next_value_node(0, self.clamp(range), 0, value, true).map(T::from_u64)
+    }
+
+    /// Get the smallest symbol in `range` on `level` which is at least `value`
+    /// `prefix` contains the bits of the symbols in the range, `tight` whether they are equal to the prefix of `value`.
+    fn next_value_node(&self, level: usize, range: Range<usize>, prefix: u64, value: u64, tight: bool) -> Option<u64> {
+        if range.is_empty() {
+            return None;
+        }
+        if level == self.num_levels() {
+            return Some(prefix);
+        }
+        if !tight || !self.bit(value, level) {
+            let left = self.map_range(level, &range, false);
+            if let Some(symbol) = self.next_value_node(level + 1, left, prefix << 1, value, tight) {
+                return Some(symbol);
+            }
+        }
+        // every symbol on the right is larger than the prefix of value, unless value goes right as well
+        let right = self.map_range(level, &range, true);
+        self.next_value_node(level + 1, right, (prefix << 1) | 1, value, tight && self.bit(value, level))
+    }
+
+    /// Get the `k` most frequent symbols in `range` together with their number of occurrences
+    /// The symbols are ordered by decreasing frequency, symbols with the same frequency by increasing value.
+    pub fn top_k(&self, range: Range<usize>, k: usize) -> Vec<(T, usize)> {
+        let range = self.clamp(range);
+        let mut result = Vec::with_capacity(k.min(range.len()));
+        // nodes ordered by their number of symbols and then by their smallest symbol
+        let mut queue = BinaryHeap::new();
+        if !range.is_empty() {
+            queue.push((range.len(), Reverse(0), 0, (range.start, range.end)));
+        }
+        while result.len() < k {
+            let Some((count, Reverse(smallest), level, (start, end))) = queue.pop() else { break };
+            if level == self.num_levels() {
+                result.push((T::from_u64(smallest), count));
+                continue;
+            }
+            let remaining_bits = self.num_levels() - level - 1;
+            for bit in [false, true] {
+                let child = self.map_range(level, &(start..end), bit);
+                if !child.is_empty() {
+                    queue.push((child.len(), Reverse(smallest | ((bit as u64) << remaining_bits)), level + 1, (child.start, child.end)));
+                }
+            }
+        }
+        result
+    }
+
+    /// Iterate over the symbols
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.len).map(|i| self.access(i))
+    }
+}
+
+/// A set of points on a grid answering orthogonal range counting queries
+/// The points are sorted by their x coordinate and their y coordinates are stored in a `WaveletMatrix`.
+/// The x coordinates are stored in a `SparseBitVector` with a one for every point and a zero for every x coordinate,
+/// so the number of points left of `x` is the number of ones before the `x - 1`-th zero.
+pub struct PointGrid {
+    /// The x coordinates in unary
+    xs: SparseBitVector,
+    /// The y coordinates ordered by x coordinate
+    ys: WaveletMatrix<u32>,
+}
+
+impl PointGrid {
+    /// Creates a grid containing `points` given as `(x, y)`
+    /// Points may occur multiple times.
+    pub fn new(points: &[(u32, u32)]) -> Self {
+        let mut points = points.to_vec();
+        points.sort_unstable();
+        let max_x = points.last().map_or(0, |&(x, _)| x as usize);
+        let xs = SparseBitVector::from_positions(points.iter().enumerate().map(|(i, &(x, _))| x as usize + i), max_x + points.len() + 1);
+        let ys: Vec<u32> = points.iter().map(|&(_, y)| y).collect();
+        PointGrid {
+            xs,
+            ys: WaveletMatrix::new(&ys),
+        }
+    }
+
+    /// Get the size of the grid including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<PointGrid>() - size_of::<SparseBitVector>() - size_of::<WaveletMatrix<u32>>()
+        + self.xs.get_size() + self.ys.get_size()
+    }
+
+    /// Get the number of points
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ys.len()
+    }
+
+    /// Whether there are no points
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ys.is_empty()
+    }
+
+    /// Get the number of points whose x coordinate is smaller than `x`
+    #[inline]
+    fn points_before(&self, x: u32) -> usize {
+        if x == 0 {
+            return 0;
+        }
+        // the points before the `x - 1`-th zero have an x coordinate of at most `x - 1`
+        let x = x as usize - 1;
+        self.xs.select0(x).map_or(self.len(), |position| position - x)
+    }
+
+    /// Get the number of points inside the rectangle `xs` times `ys`
+    pub fn count(&self, xs: Range<u32>, ys: Range<u32>) -> usize {
+        if xs.is_empty() {
+            return 0;
+        }
+        self.ys.range_count(self.points_before(xs.start)..self.points_before(xs.end), ys)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::wavelet_matrix::{PointGrid, WaveletMatrix};
+
+    fn test_against_naive(symbols: &[u32], alphabet: u32) {
+        let matrix = WaveletMatrix::new(symbols);
+        assert_eq!(matrix.len(), symbols.len());
+        assert_eq!(matrix.iter().collect::<Vec<u32>>(), symbols);
+        for symbol in 0..alphabet + 2 {
+            let positions: Vec<usize> = (0..symbols.len()).filter(|&i| symbols[i] == symbol).collect();
+            for i in (0..=symbols.len()).step_by(7) {
+                assert_eq!(matrix.rank(symbol, i), positions.iter().filter(|&&p| p < i).count());
+            }
+            for (k, &position) in positions.iter().enumerate() {
+                assert_eq!(matrix.select(symbol, k), Some(position));
+            }
+            assert_eq!(matrix.select(symbol, positions.len()), None);
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for _ in 0..200 {
+            let start = rng.gen_range(0..=symbols.len());
+            let end = rng.gen_range(start..=symbols.len());
+            let slice = &symbols[start..end];
+            let low = rng.gen_range(0..=alphabet);
+            let high = rng.gen_range(low..=alphabet + 1);
+            assert_eq!(matrix.range_count(start..end, low..high), slice.iter().filter(|&&s| (low..high).contains(&s)).count());
+            assert_eq!(matrix.next_value(start..end, low), slice.iter().filter(|&&s| s >= low).min().copied());
+            let mut frequencies: Vec<(u32, usize)> = (0..=alphabet)
+                .map(|symbol| (symbol, slice.iter().filter(|&&s| s == symbol).count()))
+                .filter(|&(_, count)| count > 0)
+                .collect();
+            frequencies.sort_by_key(|&(symbol, count)| (std::cmp::Reverse(count), symbol));
+            let k = rng.gen_range(0..=frequencies.len() + 1);
+            frequencies.truncate(k);
+            assert_eq!(matrix.top_k(start..end, k), frequencies);
+        }
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let symbols: Vec<u32> = (0..2000).map(|_| rng.gen_range(0..50)).collect();
+        test_against_naive(&symbols, 50);
+        let symbols: Vec<u32> = (0..300).map(|_| rng.gen_range(0..5)).collect();
+        test_against_naive(&symbols, 5);
+    }
+
+    #[test]
+    fn test_small() {
+        test_against_naive(&[], 0);
+        test_against_naive(&[0], 0);
+        test_against_naive(&[3, 3, 3], 3);
+        test_against_naive(&[1, 0, 7, 0], 7);
+    }
+
+    #[test]
+    fn test_large_alphabet() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let symbols: Vec<u32> = (0..1000).map(|_| rng.gen_range(0..u32::MAX)).collect();
+        let matrix = WaveletMatrix::new(&symbols);
+        assert_eq!(matrix.iter().collect::<Vec<u32>>(), symbols);
+        for (i, &symbol) in symbols.iter().enumerate() {
+            assert_eq!(matrix.select(symbol, matrix.rank(symbol, i)), Some(i));
+        }
+        let mut sorted = symbols.clone();
+        sorted.sort();
+        assert_eq!(matrix.next_value(0..symbols.len(), sorted[500]), Some(sorted[500]));
+        assert_eq!(matrix.next_value(0..symbols.len(), sorted[500] + 1), Some(sorted[501]));
+        assert_eq!(matrix.range_count(0..symbols.len(), sorted[100]..sorted[900]), 800);
+    }
+
+    #[test]
+    fn test_point_grid() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let points: Vec<(u32, u32)> = (0..1000).map(|_| (rng.gen_range(0..300), rng.gen_range(0..300))).collect();
+        let grid = PointGrid::new(&points);
+        assert_eq!(grid.len(), points.len());
+        for _ in 0..500 {
+            let x_start = rng.gen_range(0..320);
+            let x_end = rng.gen_range(x_start..=320);
+            let y_start = rng.gen_range(0..320);
+            let y_end = rng.gen_range(y_start..=320);
+            let count = points.iter().filter(|&&(x, y)| (x_start..x_end).contains(&x) && (y_start..y_end).contains(&y)).count();
+            assert_eq!(grid.count(x_start..x_end, y_start..y_end), count);
+        }
+        let empty = PointGrid::new(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.count(0..10, 0..10), 0);
+    }
+}
@@ -0,0 +1,335 @@
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::Range;
+use crate::{BitVector, IndexedBitVector};
+
+/// An unsigned integer type which can be stored in wavelet structures
+pub trait Symbol: Copy {
+    /// Converts the symbol into an integer
+    fn to_u64(self) -> u64;
+    /// Converts an integer created using `to_u64` back into a symbol
+    fn from_u64(value: u64) -> Self;
+}
+
+/// Implements `Symbol` for unsigned integer types
+macro_rules! impl_symbol {
+    ($($symbol:ty),*) => {$(
+        impl Symbol for $symbol {
+            #[inline]
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+
+            #[inline]
+            fn from_u64(value: u64) -> Self {
+                value as $symbol
+            }
+        }
+    )*};
+}
+
+impl_symbol!(u8, u16, u32, u64);
+
+/// Get the number of bits required to store all values up to `max`, but at least one
+pub(crate) fn bits_for(max: u64) -> usize {
+    max.checked_ilog2().map_or(1, |log| log as usize + 1)
+}
+
+/// A wavelet tree over a sequence of symbols
+/// The tree is stored level by level. Level `l` contains the `l`-th most significant bit of every symbol,
+/// with the symbols ordered by their `l` most significant bits.
+/// Thus, every node of level `l` is a range of the level and its children are the same range on level `l + 1`,
+/// the left child first. Navigating the tree only requires rank and select on the levels.
+pub struct WaveletTree<T: Symbol> {
+    /// The number of symbols
+    len: usize,
+    /// One bit vector per bit of the symbols, starting with the most significant bit
+    levels: Vec<IndexedBitVector>,
+    /// The stored symbol type
+    symbol: PhantomData<T>,
+}
+
+impl<T: Symbol> WaveletTree<T> {
+    /// Creates a wavelet tree of `symbols`
+    pub fn new(symbols: &[T]) -> Self {
+        let max = symbols.iter().map(|symbol| symbol.to_u64()).max().unwrap_or(0);
+        let num_levels = bits_for(max);
+        let mut current: Vec<u64> = symbols.iter().map(|symbol| symbol.to_u64()).collect();
+        let mut levels = Vec::with_capacity(num_levels);
+        for level in 0..num_levels {
+            let shift = num_levels - level - 1;
+            levels.push(current.iter().map(|&symbol| (symbol >> shift) & 1 == 1).collect::<BitVector>().build());
+            // a stable sort by the next prefix splits every node into its children without changing the order inside them
+            current.sort_by_key(|&symbol| symbol >> shift);
+        }
+        WaveletTree {
+            len: symbols.len(),
+            levels,
+            symbol: PhantomData,
+        }
+    }
+
+    /// Get the size of the wavelet tree including space on the heap
+    pub fn get_size(&self) -> usize {
+        size_of::<WaveletTree<T>>() + self.levels.iter().map(|level| size_of::<IndexedBitVector>() + level.get_size()).sum::<usize>()
+    }
+
+    /// Get the number of symbols
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no symbols
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of bits per symbol
+    #[inline]
+    fn num_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Get the bit of `symbol` on `level`
+    #[inline]
+    fn bit(&self, symbol: u64, level: usize) -> bool {
+        (symbol >> (self.num_levels() - level - 1)) & 1 == 1
+    }
+
+    /// Get the child range of the `node` on `level` and map `index` into it
+    /// `index` has to be inside `node` or its end.
+    #[inline]
+    fn child(&self, level: usize, node: &Range<usize>, index: usize, bit: bool) -> (Range<usize>, usize) {
+        let bit_vector = &self.levels[level];
+        let zeros_before = bit_vector.rank0(node.start);
+        let zeros = bit_vector.rank0(node.end) - zeros_before;
+        if bit {
+            let start = node.start + zeros;
+            (start..node.end, start + bit_vector.rank1(index) - (node.start - zeros_before))
+        } else {
+            (node.start..node.start + zeros, node.start + bit_vector.rank0(index) - zeros_before)
+        }
+    }
+
+    /// Get the symbol at `index`
+    pub fn access(&self, index: usize) -> T {
+        assert!(index < self.len, "index out of bounds: the len is {} but the index is {index}", self.len);
+        let mut node = 0..self.len;
+        let mut index = index;
+        let mut symbol = 0;
+        for level in 0..self.num_levels() {
+            let bit = self.levels[level].access(index) == 1;
+            symbol = (symbol << 1) | bit as u64;
+            (node, index) = self.child(level, &node, index, bit);
+        }
+        T::from_u64(symbol)
+    }
+
+    /// Get the number of occurrences of `symbol` before `index`, i.e. in `0..index`
+    pub fn rank(&self, symbol: T, index: usize) -> usize {
+        let symbol = symbol.to_u64();
+        if bits_for(symbol) > self.num_levels() {
+            return 0;
+        }
+        let mut node = 0..self.len;
+        let mut index = index.min(self.len);
+        for level in 0..self.num_levels() {
+            (node, index) = self.child(level, &node, index, self.bit(symbol, level));
+        }
+        index - node.start
+    }
+
+    /// Get the position of the `index`-th occurrence of `symbol`, counting from zero
+    /// Returns `None` if `symbol` occurs at most `index` times.
+    pub fn select(&self, symbol: T, index: usize) -> Option<usize> {
+        let symbol = symbol.to_u64();
+        if bits_for(symbol) > self.num_levels() {
+            return None;
+        }
+        // walk down to the leaf of the symbol and remember the nodes
+        let mut nodes = Vec::with_capacity(self.num_levels() + 1);
+        nodes.push(0..self.len);
+        for level in 0..self.num_levels() {
+            let node = nodes.last().unwrap();
+            let (child, _) = self.child(level, node, node.start, self.bit(symbol, level));
+            nodes.push(child);
+        }
+        let leaf = nodes.last().unwrap();
+        if index >= leaf.len() {
+            return None;
+        }
+        // walk up and map the position into the parent using select
+        let mut position = leaf.start + index;
+        for level in (0..self.num_levels()).rev() {
+            let bit_vector = &self.levels[level];
+            let node = &nodes[level];
+            let child = &nodes[level + 1];
+            let index_in_child = position - child.start;
+            position = if self.bit(symbol, level) {
+                bit_vector.select1(bit_vector.rank1(node.start) + index_in_child)
+            } else {
+                bit_vector.select0(bit_vector.rank0(node.start) + index_in_child)
+            }.expect("No ith zero/one found");
+        }
+        Some(position)
+    }
+
+    /// Get the number of symbols in `range` which are inside `values`
+    /// `values` excludes its end, so a `Range<T>` can never include the largest value of `T`.
+    pub fn range_count(&self, range: Range<usize>, values: Range<T>) -> usize {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+        let values = values.start.to_u64()..values.end.to_u64();
+        if range.is_empty() || values.is_empty() {
+            return 0;
+        }
+        self.range_count_node(0, 0..self.len, range, 0, &values)
+    }
+
+    /// Count the symbols in `range` of the `node` on `level` which are inside `values`
+    /// `prefix` contains the bits of the symbols in the node.
+    fn range_count_node(&self, level: usize, node: Range<usize>, range: Range<usize>, prefix: u64, values: &Range<u64>) -> usize {
+        if range.is_empty() {
+            return 0;
+        }
+        // the smallest and largest symbol which can be in the node
+        let remaining_bits = self.num_levels() - level;
+        // the root of a tree of 64 levels covers all symbols, so the shifts may be by 64 bits
+        let smallest = prefix.checked_shl(remaining_bits as u32).unwrap_or(0);
+        let largest = smallest | u64::MAX.checked_shr((u64::BITS as usize - remaining_bits) as u32).unwrap_or(0);
+        if smallest >= values.end || largest < values.start {
+            return 0;
+        }
+        if values.start <= smallest && largest < values.end {
+            return range.len();
+        }
+        let (left, left_start) = self.child(level, &node, range.start, false);
+        let (_, left_end) = self.child(level, &node, range.end, false);
+        let (right, right_start) = self.child(level, &node, range.start, true);
+        let (_, right_end) = self.child(level, &node, range.end, true);
+        self.range_count_node(level + 1, left, left_start..left_end, prefix << 1, values)
+            + self.range_count_node(level + 1, right, right_start..right_end, (prefix << 1) | 1, values)
+    }
+
+    /// Get the `k`-th smallest symbol in `range`, counting from zero
+    /// Returns `None` if `range` contains at most `k` symbols.
+    pub fn quantile(&self, range: Range<usize>, k: usize) -> Option<T> {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+        if k >= range.len() {
+            return None;
+        }
+        let mut node = 0..self.len;
+        let mut range = range;
+        let mut k = k;
+        let mut symbol = 0;
+        for level in 0..self.num_levels() {
+            let bit_vector = &self.levels[level];
+            let zeros = bit_vector.rank0(range.end) - bit_vector.rank0(range.start);
+            // go left if the k-th smallest symbol has a zero on this level
+            let bit = k >= zeros;
+            if bit {
+                k -= zeros;
+            }
+            let (child, start) = self.child(level, &node, range.start, bit);
+            let (_, end) = self.child(level, &node, range.end, bit);
+            node = child;
+            range = start..end;
+            symbol = (symbol << 1) | bit as u64;
+        }
+        Some(T::from_u64(symbol))
+    }
+
+    /// Iterate over the symbols
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.len).map(|i| self.access(i))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::wavelet_tree::{Symbol, WaveletTree};
+
+    fn test_against_naive<T: Symbol + PartialEq + std::fmt::Debug>(symbols: &[T], alphabet: u64) {
+        let tree = WaveletTree::new(symbols);
+        assert_eq!(tree.len(), symbols.len());
+        assert_eq!(tree.iter().collect::<Vec<T>>(), symbols);
+        // `from_u64` truncates, so the values beyond the alphabet must still fit into `T`
+        let max = T::from_u64(u64::MAX).to_u64();
+        let beyond = (alphabet + 1).min(max);
+        for value in 0..=beyond {
+            let symbol = T::from_u64(value);
+            let positions: Vec<usize> = (0..symbols.len()).filter(|&i| symbols[i].to_u64() == value).collect();
+            for i in (0..=symbols.len()).step_by(7) {
+                assert_eq!(tree.rank(symbol, i), positions.iter().filter(|&&p| p < i).count());
+            }
+            for (k, &position) in positions.iter().enumerate() {
+                assert_eq!(tree.select(symbol, k), Some(position));
+            }
+            assert_eq!(tree.select(symbol, positions.len()), None);
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        for _ in 0..200 {
+            let start = rng.gen_range(0..=symbols.len());
+            let end = rng.gen_range(start..=symbols.len());
+            let low = rng.gen_range(0..=alphabet);
+            let high = rng.gen_range(low..=beyond);
+            let count = symbols[start..end].iter().filter(|symbol| (low..high).contains(&symbol.to_u64())).count();
+            assert_eq!(tree.range_count(start..end, T::from_u64(low)..T::from_u64(high)), count);
+            let mut sorted: Vec<u64> = symbols[start..end].iter().map(|symbol| symbol.to_u64()).collect();
+            sorted.sort();
+            for (k, &value) in sorted.iter().enumerate() {
+                assert_eq!(tree.quantile(start..end, k), Some(T::from_u64(value)));
+            }
+            assert_eq!(tree.quantile(start..end, sorted.len()), None);
+        }
+    }
+
+    #[test]
+    fn test_u8() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let symbols: Vec<u8> = (0..2000).map(|_| rng.gen_range(0..=200)).collect();
+        test_against_naive(&symbols, 200);
+        let text = b"abracadabra mississippi";
+        test_against_naive(text, 255);
+        // the largest end of a `Range<u8>` is 255 and not 256
+        assert_eq!(WaveletTree::new(text).range_count(0..text.len(), 100..255), 14);
+    }
+
+    #[test]
+    fn test_u32() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234567);
+        let symbols: Vec<u32> = (0..1000).map(|_| rng.gen_range(0..37)).collect();
+        test_against_naive(&symbols, 37);
+        // a large alphabet with few distinct symbols
+        let symbols: Vec<u32> = (0..500).map(|_| rng.gen_range(0..4) * 1000000).collect();
+        let tree = WaveletTree::new(&symbols);
+        assert_eq!(tree.iter().collect::<Vec<u32>>(), symbols);
+        assert_eq!(tree.rank(2000000, symbols.len()), symbols.iter().filter(|&&s| s == 2000000).count());
+        assert_eq!(tree.range_count(0..symbols.len(), 1..3000000), symbols.iter().filter(|&&s| (1..3000000).contains(&s)).count());
+    }
+
+    #[test]
+    fn test_u64_high_bits() {
+        let symbols = [1, u64::MAX, 5, 1 << 63, 7];
+        let tree = WaveletTree::new(&symbols);
+        assert_eq!(tree.iter().collect::<Vec<u64>>(), symbols);
+        assert_eq!(tree.range_count(0..5, 0..10), 3);
+        assert_eq!(tree.range_count(0..5, 1 << 63..u64::MAX), 1);
+        assert_eq!(tree.range_count(1..4, 0..u64::MAX), 2);
+        assert_eq!(tree.quantile(0..5, 4), Some(u64::MAX));
+        assert_eq!(tree.rank(1 << 63, 5), 1);
+        assert_eq!(tree.select(u64::MAX, 0), Some(1));
+    }
+
+    #[test]
+    fn test_small() {
+        test_against_naive::<u8>(&[], 0);
+        test_against_naive::<u8>(&[0], 0);
+        test_against_naive::<u8>(&[1, 1, 1], 1);
+        test_against_naive::<u32>(&[5, 0, 5], 5);
+    }
+}